@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use chrono::Local;
+use serde_json::json;
+use std::error::Error;
+use std::fmt::Debug;
+
+use super::{file_action::FileAction, http_action::HttpAction};
+use crate::models::general::{
+    action::{ActionConfig, ActionMode, ActionRequest, ActionResult, ActionStatus, HandlerConfig},
+    escalation::match_action,
+    support_case::SupportCase,
+};
+
+// Handler that carries out an escalation action
+#[async_trait]
+pub trait Action: Debug + Send + Sync {
+    // Identifies the handler in action results
+    fn name(&self) -> String;
+
+    // What 'execute' would do, for dry runs
+    fn describe(&self, request: &ActionRequest) -> String;
+
+    async fn execute(
+        &self,
+        request: &ActionRequest,
+    ) -> Result<String, Box<dyn Error + Send + Sync>>;
+}
+
+pub fn handler_from_config(config: &HandlerConfig) -> Box<dyn Action> {
+    match config {
+        HandlerConfig::File { directory } => Box::new(FileAction::new(directory)),
+        HandlerConfig::Http {
+            url,
+            method,
+            timeout_secs,
+        } => Box::new(HttpAction::new(url, method, *timeout_secs)),
+    }
+}
+
+// Handlers registered per action name, plus a fallback for unrouted actions
+#[derive(Debug)]
+pub struct ActionRegistry {
+    mode: ActionMode,
+    routes: Vec<(String, Box<dyn Action>)>,
+    default_handler: Option<Box<dyn Action>>,
+}
+
+impl ActionRegistry {
+    pub fn new(mode: ActionMode) -> Self {
+        Self {
+            mode,
+            routes: Vec::from([]),
+            default_handler: None,
+        }
+    }
+
+    pub fn from_config(config: &ActionConfig) -> Self {
+        let mut registry: ActionRegistry = Self::new(config.mode);
+        for route in &config.routes {
+            registry.register(&route.action, handler_from_config(&route.handler));
+        }
+        if let Some(handler) = &config.default_handler {
+            registry.set_default_handler(handler_from_config(handler));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, action: &str, handler: Box<dyn Action>) {
+        self.routes.push((action.to_string(), handler));
+    }
+
+    pub fn set_default_handler(&mut self, handler: Box<dyn Action>) {
+        self.default_handler = Some(handler);
+    }
+
+    // Registered handler whose action name matches best, else the default handler
+    pub fn handler_for(&self, action: &str) -> Option<&dyn Action> {
+        let names: Vec<String> = self.routes.iter().map(|(name, _)| name.clone()).collect();
+        match match_action(action, &names) {
+            Some((name, _)) => self
+                .routes
+                .iter()
+                .find(|(registered, _)| *registered == name)
+                .map(|(_, handler)| handler.as_ref()),
+            None => self.default_handler.as_deref(),
+        }
+    }
+
+    // Runs or plans the action for the case and records the result on it
+    pub async fn run(
+        &self,
+        support_case: &mut SupportCase,
+        action: &str,
+        reason: &str,
+    ) -> ActionResult {
+        let request: ActionRequest = ActionRequest::from_case(support_case, action, reason);
+        let handler: Option<&dyn Action> = self.handler_for(action);
+        let (status, output): (ActionStatus, String) = match (handler, self.mode) {
+            (None, _) => (
+                ActionStatus::Planned,
+                "No handler registered, recorded only".to_string(),
+            ),
+            (Some(handler), ActionMode::DryRun) => {
+                (ActionStatus::Planned, handler.describe(&request))
+            }
+            (Some(handler), ActionMode::Execute) => match handler.execute(&request).await {
+                Ok(output) => (ActionStatus::Succeeded, output),
+                Err(e) => (ActionStatus::Failed, e.to_string()),
+            },
+        };
+        let result: ActionResult = ActionResult {
+            action: action.to_string(),
+            handler: handler
+                .map(|handler| handler.name())
+                .unwrap_or("-".to_string()),
+            mode: self.mode,
+            status,
+            parameters: json!(request),
+            output,
+            at: Local::now(),
+        };
+        support_case.action_results.push(result.clone());
+        support_case.updated();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    // Answers one request with 'status' and returns what it received
+    async fn mock_endpoint(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url: String = format!("http://{}/actions", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received: Vec<u8> = Vec::from([]);
+            let mut buffer = [0u8; 4096];
+            // Read until the JSON body is complete
+            while !String::from_utf8_lossy(&received).ends_with('}') {
+                let read: usize = socket.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buffer[..read]);
+            }
+            let answer: String = format!(
+                "HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+                status
+            );
+            socket.write_all(answer.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&received).to_string()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn tests_action_registry() {
+        let directory: PathBuf = std::env::temp_dir().join(format!("actions-{}", Uuid::new_v4()));
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+
+        // Dry runs describe the action without touching the file system
+        let mut registry: ActionRegistry = ActionRegistry::new(ActionMode::DryRun);
+        registry.set_default_handler(Box::new(FileAction::new(&directory.to_string_lossy())));
+        let planned: ActionResult = registry
+            .run(&mut support_case, "Change room", "Guest is upset")
+            .await;
+        assert_eq!(planned.status, ActionStatus::Planned);
+        assert!(!directory.exists());
+
+        let mut registry: ActionRegistry = ActionRegistry::new(ActionMode::Execute);
+        registry.set_default_handler(Box::new(FileAction::new(&directory.to_string_lossy())));
+        let (url, server) = mock_endpoint("200 OK").await;
+        registry.register("Call mechanic", Box::new(HttpAction::new(&url, "post", 5)));
+
+        let written: ActionResult = registry
+            .run(&mut support_case, "Change room", "Guest is upset")
+            .await;
+        assert_eq!(written.status, ActionStatus::Succeeded);
+        let file: PathBuf = directory.join(format!("{}-change-room.json", support_case.case_id));
+        let request: ActionRequest =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(request.reason, "Guest is upset");
+
+        let called: ActionResult = registry
+            .run(&mut support_case, "call the mechanic", "AC broken")
+            .await;
+        assert_eq!(called.status, ActionStatus::Succeeded, "{}", called.output);
+        assert!(called.handler.starts_with("http:POST"));
+        let received: String = server.await.unwrap();
+        assert!(received.starts_with("POST /actions"));
+        assert!(received.contains("\"action\":\"call the mechanic\""));
+
+        let (url, server) = mock_endpoint("503 Service Unavailable").await;
+        registry.register(
+            "Call room service",
+            Box::new(HttpAction::new(&url, "POST", 5)),
+        );
+        let failed: ActionResult = registry
+            .run(&mut support_case, "Call room service", "Hungry")
+            .await;
+        assert_eq!(failed.status, ActionStatus::Failed);
+        server.await.unwrap();
+
+        assert_eq!(support_case.action_results.len(), 4);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
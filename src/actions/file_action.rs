@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::action::Action;
+use crate::models::general::action::ActionRequest;
+
+// Writes each request to '<directory>/<case_id>-<action>.json', f.e. for a local work queue
+#[derive(Debug)]
+pub struct FileAction {
+    directory: String,
+}
+
+impl FileAction {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: directory.to_string(),
+        }
+    }
+
+    fn path_for(&self, request: &ActionRequest) -> PathBuf {
+        let slug: String = request
+            .action
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>()
+            .join("-");
+        Path::new(&self.directory).join(format!("{}-{}.json", request.case_id, slug))
+    }
+}
+
+#[async_trait]
+impl Action for FileAction {
+    fn name(&self) -> String {
+        format!("file:{}", self.directory)
+    }
+
+    fn describe(&self, request: &ActionRequest) -> String {
+        format!(
+            "Would write '{}' to {}",
+            request.action,
+            self.path_for(request).display()
+        )
+    }
+
+    async fn execute(
+        &self,
+        request: &ActionRequest,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let path: PathBuf = self.path_for(request);
+        fs::create_dir_all(&self.directory)?;
+        fs::write(&path, serde_json::to_string_pretty(request)?)?;
+        Ok(format!("Wrote '{}' to {}", request.action, path.display()))
+    }
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use reqwest::{Client, Method, Response};
+use std::error::Error;
+use std::time::Duration;
+
+use super::action::Action;
+use crate::models::general::action::ActionRequest;
+
+// Longest part of the endpoint's answer kept in the action result
+const MAX_RESPONSE_CHARS: usize = 500;
+
+// Sends each request as JSON to a configured endpoint, f.e. a local facility service
+#[derive(Debug)]
+pub struct HttpAction {
+    url: String,
+    method: String,
+    timeout: Duration,
+}
+
+impl HttpAction {
+    pub fn new(url: &str, method: &str, timeout_secs: u64) -> Self {
+        Self {
+            url: url.to_string(),
+            method: method.to_uppercase(),
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+}
+
+#[async_trait]
+impl Action for HttpAction {
+    fn name(&self) -> String {
+        format!("http:{} {}", self.method, self.url)
+    }
+
+    fn describe(&self, request: &ActionRequest) -> String {
+        format!(
+            "Would send '{}' to {} {}",
+            request.action, self.method, self.url
+        )
+    }
+
+    async fn execute(
+        &self,
+        request: &ActionRequest,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let method: Method = Method::from_bytes(self.method.as_bytes())?;
+        let client: Client = Client::builder().timeout(self.timeout).build()?;
+        let response: Response = client
+            .request(method, &self.url)
+            .json(request)
+            .send()
+            .await?;
+        let status = response.status();
+        let body: String = response.text().await?;
+        let body: String = body.chars().take(MAX_RESPONSE_CHARS).collect();
+        if !status.is_success() {
+            return Err(format!("{} answered {}: {}", self.url, status, body).into());
+        }
+        Ok(format!("{} answered {}: {}", self.url, status, body))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod action;
+pub mod file_action;
+pub mod http_action;
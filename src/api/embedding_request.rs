@@ -0,0 +1,74 @@
+use dotenv::dotenv;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest {
+    input: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+// Embedding of a text from the deployment in AZURE_OPEN_AI_EMBEDDING_DEPLOYMENT_NAME
+pub async fn call_embedding(text: &str) -> Result<Vec<f32>, Box<dyn Error + Send>> {
+    dotenv().ok();
+
+    let endpoint: String =
+        dotenv::var("AZURE_OPEN_AI_ENDPOINT").expect("AZURE_OPEN_AI_ENDPOINT missing from .env");
+    let key: String =
+        dotenv::var("AZURE_OPEN_AI_KEY").expect("AZURE_OPEN_AI_KEY missing from .env");
+    let deployment: String = dotenv::var("AZURE_OPEN_AI_EMBEDDING_DEPLOYMENT_NAME")
+        .expect("AZURE_OPEN_AI_EMBEDDING_DEPLOYMENT_NAME missing from .env");
+    let api_version: String = dotenv::var("AZURE_OPEN_AI_API_VERSION")
+        .expect("AZURE_OPEN_AI_API_VERSION missing from .env");
+
+    let url = &format!(
+        "{}/openai/deployments/{}/embeddings?api-version={}",
+        endpoint, deployment, api_version
+    );
+
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.append(
+        "Content-Type",
+        HeaderValue::from_str("application/json").unwrap(),
+    );
+    headers.append("api-key", HeaderValue::from_str(&key).unwrap());
+
+    let client: Client = Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|e| -> Box<dyn Error + Send> { Box::new(e) })?;
+
+    let response: EmbeddingResponse = client
+        .post(url)
+        .json(&EmbeddingRequest {
+            input: text.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| -> Box<dyn Error + Send> { Box::new(e) })?
+        .json()
+        .await
+        .map_err(|e| -> Box<dyn Error + Send> { Box::new(e) })?;
+
+    response
+        .data
+        .into_iter()
+        .next()
+        .map(|data| data.embedding)
+        .ok_or_else(|| -> Box<dyn Error + Send> {
+            Box::new(std::io::Error::other("Embedding response without data"))
+        })
+}
@@ -7,6 +7,14 @@ use reqwest::{
 use std::error::Error;
 
 pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn Error + Send>> {
+    let api_response: APIResponse = call_gpt_completion(messages).await?;
+    Ok(api_response.choices[0].message.content.clone())
+}
+
+// Full completion including model and token usage
+pub async fn call_gpt_completion(
+    messages: Vec<Message>,
+) -> Result<APIResponse, Box<dyn Error + Send>> {
     dotenv().ok();
 
     let endpoint: String =
@@ -51,7 +59,7 @@ pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn Error +
         .await
         .map_err(|e| -> Box<dyn Error + Send> { Box::new(e) })?;
 
-    Ok(api_response.choices[0].message.content.clone())
+    Ok(api_response)
 }
 
 #[cfg(test)]
@@ -1 +1,2 @@
-pub mod gpt_request;
+pub mod embedding_request;
+pub mod gpt_request;
\ No newline at end of file
@@ -0,0 +1,618 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use std::error::Error;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::helpers::case_io::{export_case, import_case};
+use crate::helpers::command_line::CLIPrint;
+use crate::models::general::{
+    action::ActionStatus,
+    attachment::{Attachment, AttachmentConfig},
+    audit::{verify_log, AuditEntry},
+    case_status::CaseStatus,
+    customer::{ContactChannel, ContactKind, Customer},
+    notification::DeliveryStatus,
+    priority::PriorityConfig,
+    sentiment::{SentimentConfig, SentimentTrend},
+    similarity::{CaseLink, SimilarityConfig},
+    sla::{SlaConfig, SlaEvaluation, SlaState},
+    support_case::SupportCase,
+};
+use crate::storage::{
+    attachment_store::AttachmentStore,
+    audit_log::record_case,
+    case_store::{CaseFilter, CaseStore},
+};
+
+// Parse 'YYYY-MM-DD' into local start (or end) of that day
+fn parse_day(value: &str, end_of_day: bool) -> Result<DateTime<Local>, Box<dyn Error>> {
+    let day: NaiveDate = NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+    let time = if end_of_day {
+        day.and_hms_milli_opt(23, 59, 59, 999)
+    } else {
+        day.and_hms_opt(0, 0, 0)
+    }
+    .ok_or("Invalid time of day")?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .ok_or_else(|| format!("Invalid local date '{}'", value).into())
+}
+
+fn parse_id(value: Option<&String>) -> Result<Uuid, Box<dyn Error>> {
+    let value: &String = value.ok_or("Missing id")?;
+    Ok(Uuid::parse_str(value)?)
+}
+
+pub fn parse_case_filter(args: &[String]) -> Result<CaseFilter, Box<dyn Error>> {
+    let mut filter: CaseFilter = CaseFilter::default();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value: &String = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--status" => filter.status = Some(value.parse()?),
+            "--sentiment" => filter.sentiment = Some(value.parse()?),
+            "--escalated" => filter.escalated = Some(value.parse()?),
+            "--from" => filter.created_from = Some(parse_day(value, false)?),
+            "--to" => filter.created_to = Some(parse_day(value, true)?),
+            _ => return Err(format!("Unknown filter '{}'", flag).into()),
+        }
+    }
+    Ok(filter)
+}
+
+fn print_case_line(support_case: &SupportCase) {
+    CLIPrint::Default.out(
+        "Case",
+        format!(
+            "{} | {} | {} | {} | {} | {} | {}",
+            support_case.case_id,
+            support_case.priority,
+            support_case.status,
+            support_case
+                .sentiment
+                .as_ref()
+                .map(|sentiment| sentiment.polarity.as_str())
+                .unwrap_or("-"),
+            support_case
+                .intent
+                .as_ref()
+                .map(|intent| intent.kind.as_str())
+                .unwrap_or("-"),
+            support_case.created_at.format("%Y-%m-%d %H:%M"),
+            support_case.customer_query
+        )
+        .as_str(),
+    );
+}
+
+// export <case_id> <path>
+pub fn export_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let path: &String = args.get(1).ok_or("Usage: export <case_id> <path>")?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    export_case(&support_case, path)?;
+    CLIPrint::Info.out("System", format!("Case exported to {}", path).as_str());
+    Ok(())
+}
+
+// import <path>
+pub fn import_command(store: &mut dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path: &String = args.first().ok_or("Usage: import <path>")?;
+    let support_case: SupportCase = import_case(path)?;
+    record_case(store, &support_case, "Import")?;
+    CLIPrint::Info.out(
+        "System",
+        format!("Imported case {}", support_case.case_id).as_str(),
+    );
+    Ok(())
+}
+
+// show <case_id>
+pub fn show_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    CLIPrint::Default.out("Case", format!("{:#?}", support_case).as_str());
+    Ok(())
+}
+
+// sentiment <case_id>
+pub fn sentiment_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    for point in &support_case.sentiment_trajectory {
+        CLIPrint::Default.out(
+            "Sentiment",
+            format!(
+                "#{} | {} | {} | confidence {:.2} | {}",
+                point.message_index,
+                point.at.format("%Y-%m-%d %H:%M"),
+                point.sentiment,
+                point.sentiment.confidence,
+                point.sentiment.rationale
+            )
+            .as_str(),
+        );
+    }
+    let trend: SentimentTrend = SentimentConfig::load().trend(&support_case.sentiment_trajectory);
+    CLIPrint::Info.out("Sentiment", format!("Trend: {}", trend).as_str());
+    Ok(())
+}
+
+// attach <case_id> <path>...
+pub fn attach_command(store: &mut dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let paths: &[String] = args.get(1..).unwrap_or(&[]);
+    if paths.is_empty() {
+        return Err("Usage: attach <case_id> <path>...".into());
+    }
+    let mut support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+
+    let attachment_store: AttachmentStore = AttachmentStore::new(AttachmentConfig::load());
+    for path in paths {
+        let attachment: Attachment = attachment_store.save(&case_id, Path::new(path))?;
+        CLIPrint::Info.out(
+            "System",
+            format!(
+                "Attached {} ({}, {} bytes, text {})",
+                attachment.file_name,
+                attachment.mime_type,
+                attachment.size_bytes,
+                if attachment.extracted_text.is_some() { "extracted" } else { "not extracted" }
+            )
+            .as_str(),
+        );
+        support_case.attachments.push(attachment);
+    }
+    support_case.updated();
+    record_case(store, &support_case, "Operator")
+}
+
+// attachments <case_id>: list attachments and check them against their checksums
+pub fn attachments_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    let attachment_store: AttachmentStore = AttachmentStore::new(AttachmentConfig::load());
+    for attachment in &support_case.attachments {
+        let line: String = format!(
+            "{} | {} | {} bytes | {}",
+            attachment.file_name, attachment.mime_type, attachment.size_bytes, attachment.storage_path
+        );
+        match attachment_store.read(attachment) {
+            Ok(_) => CLIPrint::Info.out("Attachment", &line),
+            Err(e) => CLIPrint::Error.out("Attachment", format!("{} | {}", line, e).as_str()),
+        }
+    }
+    Ok(())
+}
+
+// trace <case_id>: one block per model call, oldest first
+pub fn trace_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    for call in &support_case.trace {
+        let usage: String = call
+            .usage
+            .map(|usage| format!("{} tokens", usage.total_tokens))
+            .unwrap_or("usage unknown".to_string());
+        CLIPrint::Info.out(
+            &call.agent,
+            format!(
+                "{} | {} | {} ms | {} | {} retries",
+                call.started_at.format("%Y-%m-%d %H:%M:%S"),
+                call.model.as_deref().unwrap_or("-"),
+                call.latency_ms,
+                usage,
+                call.retries
+            )
+            .as_str(),
+        );
+        if let Some(truncation) = &call.truncation {
+            CLIPrint::Warning.out("Truncated", truncation.to_string().as_str());
+        }
+        CLIPrint::Default.out("Response", call.response.as_deref().unwrap_or("-"));
+        if let Some(parsed) = &call.parsed {
+            CLIPrint::Default.out("Parsed", parsed.to_string().as_str());
+        }
+        for error in &call.errors {
+            CLIPrint::Warning.out("Error", error);
+        }
+    }
+    Ok(())
+}
+
+// actions <case_id>: the escalation decision, the actions run for it, policy violations and notifications
+pub fn actions_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    if let Some(decision) = &support_case.escalation_decision {
+        CLIPrint::Info.out(
+            "Decision",
+            format!(
+                "{} | {} (match {:.2}, {} attempt(s))",
+                decision.choice, decision.reason, decision.match_score, decision.attempts
+            )
+            .as_str(),
+        );
+    }
+    for result in &support_case.action_results {
+        let level: CLIPrint = match result.status {
+            ActionStatus::Failed => CLIPrint::Warning,
+            _ => CLIPrint::Default,
+        };
+        level.out(
+            "Action",
+            format!(
+                "{} | {} | {} | {:?} | {}",
+                result.at.format("%Y-%m-%d %H:%M"),
+                result.action,
+                result.handler,
+                result.mode,
+                result.status
+            )
+            .as_str(),
+        );
+        CLIPrint::Default.out("Output", &result.output);
+    }
+    for violation in &support_case.policy_violations {
+        CLIPrint::Warning.out(
+            "Policy",
+            format!(
+                "{} | {} | {:?} {} {} | {}",
+                violation.at.format("%Y-%m-%d %H:%M"),
+                violation.policy,
+                violation.target,
+                violation.kind,
+                violation.outcome,
+                violation.detail
+            )
+            .as_str(),
+        );
+    }
+    for delivery in &support_case.notifications {
+        let level: CLIPrint = match delivery.status {
+            DeliveryStatus::Failed => CLIPrint::Warning,
+            DeliveryStatus::Delivered => CLIPrint::Default,
+        };
+        level.out(
+            "Notification",
+            format!(
+                "{} | {} | {} | {} attempt(s){}",
+                delivery.at.format("%Y-%m-%d %H:%M"),
+                delivery.channel,
+                delivery.status,
+                delivery.attempts,
+                delivery
+                    .error
+                    .as_ref()
+                    .map(|e| format!(" | {}", e))
+                    .unwrap_or_default()
+            )
+            .as_str(),
+        );
+    }
+    Ok(())
+}
+
+// handoff <case_id>: the summary written for the people taking over the case
+pub fn handoff_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    let handoff = support_case
+        .handoff
+        .ok_or_else(|| format!("Case {} has no handoff", case_id))?;
+    let source: &str = if handoff.generated {
+        "Handoff"
+    } else {
+        "Handoff (from case)"
+    };
+    CLIPrint::Default.out(source, handoff.to_string().as_str());
+    Ok(())
+}
+
+// similar <case_id>: stored cases that look like duplicates of or related to the case
+pub fn similar_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    let config: SimilarityConfig = SimilarityConfig::load();
+    let links: Vec<CaseLink> =
+        config.find_similar(&support_case, &store.list(&CaseFilter::default())?);
+    for link in &links {
+        if let Some(other) = store.get(&link.case_id)? {
+            CLIPrint::Info.out(
+                "Similar",
+                format!("{} ({:.2})", link.relation, link.score).as_str(),
+            );
+            print_case_line(&other);
+        }
+    }
+    CLIPrint::Info.out("System", format!("{} similar case(s)", links.len()).as_str());
+    Ok(())
+}
+
+// audit <case_id>: audit log entries of one case
+pub fn audit_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    for entry in store.audit_entries(Some(&case_id))? {
+        CLIPrint::Info.out(
+            &entry.actor,
+            format!(
+                "#{} | {} | {} | {}",
+                entry.sequence,
+                entry.at.format("%Y-%m-%d %H:%M:%S"),
+                entry.action,
+                entry.details
+            )
+            .as_str(),
+        );
+    }
+    Ok(())
+}
+
+// audit-verify: check the hash chain and that stored cases match their last audited version
+pub fn audit_verify_command(store: &dyn CaseStore) -> Result<(), Box<dyn Error>> {
+    let entries: Vec<AuditEntry> = store.audit_entries(None)?;
+    let cases: Vec<(Uuid, String)> = store.case_snapshots()?;
+    let problems: Vec<String> = verify_log(&entries, &cases);
+    for problem in &problems {
+        CLIPrint::Error.out("Audit", problem);
+    }
+    if !problems.is_empty() {
+        return Err(format!("Audit log verification failed with {} problem(s)", problems.len()).into());
+    }
+    CLIPrint::Info.out(
+        "Audit",
+        format!("{} entries and {} cases verified", entries.len(), cases.len()).as_str(),
+    );
+    Ok(())
+}
+
+// transition <case_id> <status> [reason]
+pub fn transition_command(store: &mut dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = parse_id(args.first())?;
+    let status: CaseStatus = args
+        .get(1)
+        .ok_or("Usage: transition <case_id> <status> [reason]")?
+        .parse()?;
+    let reason: String = args.get(2..).unwrap_or(&[]).join(" ");
+
+    let mut support_case: SupportCase = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    support_case.transition(status, "Operator", &reason)?;
+    record_case(store, &support_case, "Operator")?;
+    CLIPrint::Info.out(
+        "System",
+        format!("Case {} is now '{}'", case_id, support_case.status).as_str(),
+    );
+    Ok(())
+}
+
+// list [--status s] [--sentiment s] [--escalated true|false] [--from YYYY-MM-DD] [--to YYYY-MM-DD]
+pub fn list_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let filter: CaseFilter = parse_case_filter(args)?;
+    let cases: Vec<SupportCase> = store.list(&filter)?;
+    for support_case in &cases {
+        print_case_line(support_case);
+    }
+    CLIPrint::Info.out("System", format!("{} case(s) found", cases.len()).as_str());
+    Ok(())
+}
+
+// queue: open cases ordered by current priority, oldest first within a priority
+pub fn queue_command(store: &dyn CaseStore) -> Result<(), Box<dyn Error>> {
+    let config: PriorityConfig = PriorityConfig::load();
+    let mut cases: Vec<SupportCase> = store
+        .list(&CaseFilter::default())?
+        .into_iter()
+        .filter(|support_case| {
+            !matches!(
+                support_case.status,
+                CaseStatus::Resolved | CaseStatus::Closed
+            )
+        })
+        .collect();
+    for support_case in cases.iter_mut() {
+        support_case.reprioritize(&config);
+    }
+    cases.sort_by_key(|support_case| (support_case.priority, support_case.created_at));
+
+    for support_case in &cases {
+        print_case_line(support_case);
+    }
+    CLIPrint::Info.out("System", format!("{} open case(s)", cases.len()).as_str());
+    Ok(())
+}
+
+// sla-sweep: record breaches and list cases at risk of or in breach of their SLA
+pub fn sla_sweep_command(store: &mut dyn CaseStore) -> Result<(), Box<dyn Error>> {
+    let config: SlaConfig = SlaConfig::load();
+    let now: DateTime<Local> = Local::now();
+    let mut flagged: usize = 0;
+
+    for mut support_case in store.list(&CaseFilter::default())? {
+        let before: (bool, bool) = (
+            support_case.sla.first_response_breached,
+            support_case.sla.resolution_breached,
+        );
+        let evaluation: SlaEvaluation = support_case.refresh_sla(&config, now);
+        let after: (bool, bool) = (
+            support_case.sla.first_response_breached,
+            support_case.sla.resolution_breached,
+        );
+        if before != after {
+            record_case(store, &support_case, "SLA")?;
+        }
+        if !evaluation.needs_attention() {
+            continue;
+        }
+
+        flagged += 1;
+        let print: CLIPrint = if evaluation.first_response == SlaState::Breached
+            || evaluation.resolution == SlaState::Breached
+        {
+            CLIPrint::Error
+        } else {
+            CLIPrint::Warning
+        };
+        print.out(
+            "SLA",
+            format!(
+                "{} | {} | first response {} | resolution {} (due {})",
+                support_case.case_id,
+                support_case.priority,
+                evaluation.first_response,
+                evaluation.resolution,
+                support_case
+                    .sla
+                    .resolution_due
+                    .map(|due| due.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or("-".to_string())
+            )
+            .as_str(),
+        );
+    }
+    CLIPrint::Info.out("System", format!("{} case(s) need attention", flagged).as_str());
+    Ok(())
+}
+
+// Applies --name, --email, --phone, --chat, --social, --tier, --ltv and --language flags
+fn apply_customer_flags(customer: &mut Customer, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value: &String = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let contact_kind: Option<ContactKind> = match flag.as_str() {
+            "--email" => Some(ContactKind::Email),
+            "--phone" => Some(ContactKind::Phone),
+            "--chat" => Some(ContactKind::Chat),
+            "--social" => Some(ContactKind::Social),
+            _ => None,
+        };
+        if let Some(kind) = contact_kind {
+            customer.contact_channels.push(ContactChannel {
+                kind,
+                value: value.clone(),
+            });
+            continue;
+        }
+        match flag.as_str() {
+            "--name" => customer.name = value.clone(),
+            "--tier" => customer.tier = value.parse()?,
+            "--ltv" => customer.lifetime_value = value.parse()?,
+            "--language" => customer.language = value.clone(),
+            _ => return Err(format!("Unknown customer flag '{}'", flag).into()),
+        }
+    }
+    Ok(())
+}
+
+fn print_customer_line(customer: &Customer) {
+    let contacts: Vec<&str> = customer
+        .contact_channels
+        .iter()
+        .map(|channel| channel.value.as_str())
+        .collect();
+    CLIPrint::Default.out(
+        "Customer",
+        format!(
+            "{} | {} | {} | {:.2} | {} | {}",
+            customer.customer_id,
+            customer.name,
+            customer.tier,
+            customer.lifetime_value,
+            customer.language,
+            contacts.join(", ")
+        )
+        .as_str(),
+    );
+}
+
+// customer-add --name <name> [--email e] [--phone p] [--tier standard|premium|vip] [--ltv 0.0] [--language en]
+pub fn customer_add_command(store: &mut dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut customer: Customer = Customer::new(String::new());
+    apply_customer_flags(&mut customer, args)?;
+    if customer.name.is_empty() {
+        return Err("Usage: customer-add --name <name> [flags]".into());
+    }
+    store.create_customer(&customer)?;
+    print_customer_line(&customer);
+    Ok(())
+}
+
+// customer-set <customer_id> [flags]: updates fields and adds contact channels
+pub fn customer_set_command(store: &mut dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let customer_id: Uuid = parse_id(args.first())?;
+    let mut customer: Customer = store
+        .get_customer(&customer_id)?
+        .ok_or_else(|| format!("Customer {} not found", customer_id))?;
+    apply_customer_flags(&mut customer, args.get(1..).unwrap_or(&[]))?;
+    store.update_customer(&customer)?;
+    print_customer_line(&customer);
+    Ok(())
+}
+
+// customer-list
+pub fn customer_list_command(store: &dyn CaseStore) -> Result<(), Box<dyn Error>> {
+    let customers: Vec<Customer> = store.list_customers()?;
+    for customer in &customers {
+        print_customer_line(customer);
+    }
+    CLIPrint::Info.out("System", format!("{} customer(s) found", customers.len()).as_str());
+    Ok(())
+}
+
+// customer-show <customer_id or contact>: profile and case history
+pub fn customer_show_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let key: &String = args.first().ok_or("Usage: customer-show <customer_id or contact>")?;
+    let customer: Customer = find_customer(store, key)?
+        .ok_or_else(|| format!("Customer '{}' not found", key))?;
+    print_customer_line(&customer);
+    let cases: Vec<SupportCase> = store.list(&CaseFilter {
+        customer_id: Some(customer.customer_id),
+        ..Default::default()
+    })?;
+    for support_case in &cases {
+        print_case_line(support_case);
+    }
+    Ok(())
+}
+
+// Resolve a customer from either their id or one of their contact values
+pub fn find_customer(store: &dyn CaseStore, key: &str) -> Result<Option<Customer>, Box<dyn Error>> {
+    match Uuid::parse_str(key) {
+        Ok(customer_id) => store.get_customer(&customer_id),
+        Err(_) => store.find_customer_by_contact(key),
+    }
+}
+
+// search <text>
+pub fn search_command(store: &dyn CaseStore, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let cases: Vec<SupportCase> = store.search(&args.join(" "))?;
+    for support_case in &cases {
+        print_case_line(support_case);
+    }
+    CLIPrint::Info.out("System", format!("{} case(s) found", cases.len()).as_str());
+    Ok(())
+}
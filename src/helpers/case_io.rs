@@ -0,0 +1,18 @@
+use std::error::Error;
+use std::fs;
+
+use crate::models::general::{case_export::CaseExport, support_case::SupportCase};
+
+// Write case as versioned JSON document
+pub fn export_case(support_case: &SupportCase, path: &str) -> Result<(), Box<dyn Error>> {
+    let json: String = CaseExport::new(support_case.clone()).to_json()?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+// Read case from versioned JSON document
+pub fn import_case(path: &str) -> Result<SupportCase, Box<dyn Error>> {
+    let json: String = fs::read_to_string(path)?;
+    let export: CaseExport = CaseExport::from_json(&json)?;
+    Ok(export.case)
+}
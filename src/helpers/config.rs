@@ -0,0 +1,56 @@
+use dotenv::dotenv;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fs;
+
+use crate::helpers::command_line::CLIPrint;
+
+// Parse a JSON config file
+pub fn read_config<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn Error>> {
+    let json: String = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+// Read the file named by env (or default_path), falling back to built-in defaults
+pub fn load_config<T: DeserializeOwned + Default>(env: &str, default_path: &str) -> T {
+    dotenv().ok();
+
+    let path: String = dotenv::var(env).unwrap_or(default_path.to_string());
+    match read_config(&path) {
+        Ok(config) => config,
+        Err(e) => {
+            CLIPrint::Warning.out(
+                "System",
+                format!("Using default config for {} ({}: {})", env, path, e).as_str(),
+            );
+            T::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, Default, PartialEq)]
+    struct SampleConfig {
+        limit: u32,
+    }
+
+    #[test]
+    fn tests_load_config() {
+        let path = std::env::temp_dir().join(format!("config-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, r#"{ "limit": 7 }"#).unwrap();
+        let read: SampleConfig = read_config(path.to_str().unwrap()).unwrap();
+        assert_eq!(read.limit, 7);
+
+        // Missing and malformed files fall back to defaults
+        let missing: SampleConfig =
+            load_config("SAMPLE_CONFIG_PATH_UNSET", "config/does-not-exist.json");
+        assert_eq!(missing, SampleConfig::default());
+        fs::write(&path, "{ not json").unwrap();
+        assert!(read_config::<SampleConfig>(path.to_str().unwrap()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,3 +1,6 @@
 pub mod command_line;
 pub mod request;
-pub mod backend;
\ No newline at end of file
+pub mod backend;
+pub mod case_io;
+pub mod case_commands;
+pub mod config;
\ No newline at end of file
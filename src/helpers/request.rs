@@ -1,13 +1,26 @@
 use std::error::Error;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 use crate::{
-    api::gpt_request::call_gpt,
+    api::gpt_request::{call_gpt, call_gpt_completion},
+    helpers::command_line::CLIPrint,
     models::{
         ai::chatgpt::Message,
-        general::support_case::{ SupportCase},
+        general::{
+            context_budget::{ContextBudget, ContextBudgetConfig, ContextTruncation},
+            llm_call::LlmCall,
+            support_case::PromptContext,
+        },
     },
 };
 
+// Additional attempts after a failed model call
+const AI_CALL_RETRIES: u32 = 1;
+
+// Loaded on the first model call and shared by every agent
+static CONTEXT_BUDGET: OnceLock<ContextBudget> = OnceLock::new();
+
 pub fn prepare_message(objective: &str, context: &str, query: &str) -> Message {
     let msg_str: String = format!(
         "
@@ -35,3 +48,107 @@ pub async fn ai_request(msg: Message) -> Result<String, Box<dyn Error + Send>> {
         Err(_) => call_gpt(vec![msg.clone()]).await,
     }
 }
+
+// Performs call to ChatGPT and records request, response, timing and errors for the case trace
+pub async fn ai_call(agent: &str, prompt_template: &str, msg: Message) -> LlmCall {
+    ai_call_messages(agent, prompt_template, vec![msg]).await
+}
+
+// Same as 'ai_call' for requests with earlier messages, f.e. the agent's memory
+pub async fn ai_call_messages(
+    agent: &str,
+    prompt_template: &str,
+    messages: Vec<Message>,
+) -> LlmCall {
+    match context_budget().fit(messages.clone()) {
+        Ok((messages, truncation)) => send_call(agent, prompt_template, messages, truncation).await,
+        Err(e) => over_budget_call(agent, prompt_template, messages, e),
+    }
+}
+
+// Performs call to ChatGPT with a case's prompt context, shortened to the context budget first
+pub async fn ai_case_call(
+    agent: &str,
+    prompt_template: &str,
+    context: PromptContext,
+    query: &str,
+) -> LlmCall {
+    ai_case_call_messages(agent, prompt_template, Vec::new(), context, query).await
+}
+
+// Same as 'ai_case_call' for requests with earlier messages, f.e. the agent's memory
+pub async fn ai_case_call_messages(
+    agent: &str,
+    prompt_template: &str,
+    earlier: Vec<Message>,
+    mut context: PromptContext,
+    query: &str,
+) -> LlmCall {
+    let prompt = |context: &PromptContext| prepare_message(prompt_template, &context.text(), query);
+    match context_budget().fit_case_request(earlier.clone(), &mut context, prompt) {
+        Ok((messages, truncation)) => send_call(agent, prompt_template, messages, truncation).await,
+        Err(e) => {
+            let mut messages: Vec<Message> = earlier;
+            messages.push(prompt(&context));
+            over_budget_call(agent, prompt_template, messages, e)
+        }
+    }
+}
+
+fn context_budget() -> &'static ContextBudget {
+    CONTEXT_BUDGET.get_or_init(|| ContextBudget::new(ContextBudgetConfig::load()))
+}
+
+// A request that does not fit the budget is not sent; the trace shows why
+fn over_budget_call(
+    agent: &str,
+    prompt_template: &str,
+    messages: Vec<Message>,
+    e: String,
+) -> LlmCall {
+    CLIPrint::Warning.out(agent, e.as_str());
+    let mut call: LlmCall = LlmCall::new(agent, prompt_template, messages);
+    call.errors.push(e);
+    call
+}
+
+async fn send_call(
+    agent: &str,
+    prompt_template: &str,
+    messages: Vec<Message>,
+    truncation: Option<ContextTruncation>,
+) -> LlmCall {
+    if let Some(truncation) = &truncation {
+        CLIPrint::Warning.out(agent, format!("Request shortened: {}", truncation).as_str());
+    }
+    let mut call: LlmCall = LlmCall::new(agent, prompt_template, messages);
+    call.truncation = truncation;
+    let started: Instant = Instant::now();
+    for attempt in 0..=AI_CALL_RETRIES {
+        call.retries = attempt;
+        match call_gpt_completion(call.request.clone()).await {
+            Ok(completion) => match completion.choices.first() {
+                Some(choice) => {
+                    call.response = Some(choice.message.content.clone());
+                    call.model = completion.model;
+                    call.usage = completion.usage;
+                    break;
+                }
+                None => call.errors.push("Model returned no choices".to_string()),
+            },
+            Err(e) => call.errors.push(e.to_string()),
+        }
+    }
+    call.latency_ms = started.elapsed().as_millis() as u64;
+    call
+}
+
+// Strip markdown code fences and surrounding chatter the model adds around JSON
+pub fn extract_json(response: &str) -> &str {
+    let start: Option<usize> = response.find(['[', '{']);
+    let end: Option<usize> = response.rfind([']', '}']);
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => &response[start..=end],
+        _ => response.trim(),
+    }
+}
@@ -1,18 +1,115 @@
+use helpers::case_commands::{
+    actions_command, attach_command, attachments_command, audit_command, audit_verify_command,
+    customer_add_command, customer_list_command, customer_set_command, customer_show_command,
+    export_command, find_customer, handoff_command, import_command, list_command, queue_command,
+    search_command, sentiment_command, show_command, similar_command, sla_sweep_command,
+    trace_command, transition_command,
+};
 use helpers::command_line::{get_user_response, CLIPrint};
 use models::agents_coordinator::agent_coordinator::CoordinatorAgent;
+use std::env;
+use std::error::Error;
+use storage::case_store::{open_case_store, CaseStore};
+use uuid::Uuid;
 
+mod actions;
 mod api;
 mod helpers;
 mod models;
+mod notifications;
+mod storage;
+mod ticketing;
 
-#[tokio::main]
-async fn main() {
+async fn run_support_request(store: Box<dyn CaseStore>) {
     CLIPrint::Default.out("System", "Let's set the scene (f.e '5 Star Hotel').");
     let context: String = get_user_response("Where are we?");
+    let customer_key: String =
+        get_user_response("Who is the customer? (customer id, email or phone, empty if unknown)");
+    let customer_id: Option<Uuid> = if customer_key.is_empty() {
+        None
+    } else {
+        match find_customer(store.as_ref(), &customer_key) {
+            Ok(Some(customer)) => Some(customer.customer_id),
+            Ok(None) => {
+                CLIPrint::Warning.out("System", "Unknown customer, continuing without profile.");
+                None
+            }
+            Err(e) => {
+                CLIPrint::Error.out("System", e.to_string().as_str());
+                None
+            }
+        }
+    };
     let query: String = get_user_response("What is your ask for the customer support?");
+    let attachments: String =
+        get_user_response("Any attachments? (file paths separated by commas, empty if none)");
     CLIPrint::Default.out("System", "Thank you. \nStarting Support Request...");
     // Coordinator
     let mut coordinator_agent: CoordinatorAgent =
-        CoordinatorAgent::new(context, query);
+        CoordinatorAgent::new(context, query, customer_id, store);
+    let paths: Vec<String> = attachments
+        .split(',')
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    coordinator_agent.attach_files(&paths);
     coordinator_agent.handle_support_request().await;
 }
+
+// reply <case_id> <message>
+async fn run_customer_reply(
+    store: Box<dyn CaseStore>,
+    args: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let case_id: Uuid = Uuid::parse_str(args.first().ok_or("Missing id")?)?;
+    let message: String = args.get(1..).unwrap_or(&[]).join(" ");
+    if message.trim().is_empty() {
+        return Err("Missing message".into());
+    }
+    let support_case = store
+        .get(&case_id)?
+        .ok_or_else(|| format!("Case {} not found", case_id))?;
+    let mut coordinator_agent: CoordinatorAgent = CoordinatorAgent::from_case(support_case, store);
+    coordinator_agent.handle_customer_reply(message).await;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut store: Box<dyn CaseStore> = open_case_store().expect("Should have opened case store");
+
+    let command_args: &[String] = args.get(1..).unwrap_or(&[]);
+    let result: Result<(), Box<dyn Error>> = match args.first().map(String::as_str) {
+        Some("export") => export_command(store.as_ref(), command_args),
+        Some("import") => import_command(store.as_mut(), command_args),
+        Some("show") => show_command(store.as_ref(), command_args),
+        Some("reply") => run_customer_reply(store, command_args).await,
+        Some("sentiment") => sentiment_command(store.as_ref(), command_args),
+        Some("attach") => attach_command(store.as_mut(), command_args),
+        Some("attachments") => attachments_command(store.as_ref(), command_args),
+        Some("trace") => trace_command(store.as_ref(), command_args),
+        Some("actions") => actions_command(store.as_ref(), command_args),
+        Some("handoff") => handoff_command(store.as_ref(), command_args),
+        Some("audit") => audit_command(store.as_ref(), command_args),
+        Some("audit-verify") => audit_verify_command(store.as_ref()),
+        Some("list") => list_command(store.as_ref(), command_args),
+        Some("queue") => queue_command(store.as_ref()),
+        Some("sla-sweep") => sla_sweep_command(store.as_mut()),
+        Some("search") => search_command(store.as_ref(), command_args),
+        Some("similar") => similar_command(store.as_ref(), command_args),
+        Some("transition") => transition_command(store.as_mut(), command_args),
+        Some("customer-add") => customer_add_command(store.as_mut(), command_args),
+        Some("customer-set") => customer_set_command(store.as_mut(), command_args),
+        Some("customer-list") => customer_list_command(store.as_ref()),
+        Some("customer-show") => customer_show_command(store.as_ref(), command_args),
+        _ => {
+            run_support_request(store).await;
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        CLIPrint::Error.out("System", e.to_string().as_str());
+    }
+}
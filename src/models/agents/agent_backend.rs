@@ -12,7 +12,7 @@ use crossterm::{
 };
 use reqwest::Client;
 
-use crate::{helpers::{backend::{check_status_code, read_code_template_contents, read_executable_main_contents, save_api_endpoints, save_backend_code, WEB_SERVER_PROJECT_PATH}, command_line::{confirm_safe_code, CLIPrint}, request::{ai_request, prepare_message}}, models::{agents_common::{common_agent::{AgentState, CommonAgent}, common_traits::AgentFunctionTrait}, ai::chatgpt::Message, general::{route::Route, support_case::{self, SupportCase}}}};
+use crate::{helpers::{backend::{check_status_code, read_code_template_contents, read_executable_main_contents, save_api_endpoints, save_backend_code, WEB_SERVER_PROJECT_PATH}, command_line::{confirm_safe_code, CLIPrint}, request::{ai_call, prepare_message}}, models::{agents_common::{common_agent::{AgentState, CommonAgent}, common_traits::AgentFunctionTrait}, ai::chatgpt::Message, general::{llm_call::LlmCall, route::Route, support_case::{self, SupportCase}}}};
 
 const PROMPT_GENERATE_BACKEND:&str = r#"INPUT: Takes in a ACTIONS_DESCRIPTION and CODE_TEMPLATE for a website backend build
     IMPORTANT: The backend code is ONLY an example. If the Actions described requires it, make as many changes as you like.
@@ -140,11 +140,10 @@ impl AgentBackendDeveloper {
         );
         let msg: Message = prepare_message(&self.common.objective, &support_case.support_context, &msg_context);
 
-        let ai_response = ai_request(
-            msg,
-        )
-        .await.expect("should have returned ai response");
-        save_backend_code(&ai_response);
+        let call: LlmCall = ai_call(&self.common.role, &self.common.objective, msg).await;
+        let ai_response: Option<String> = call.response.clone();
+        support_case.trace.push(call);
+        save_backend_code(&ai_response.expect("should have returned ai response"));
     }
 
     async fn call_improved_backend_code(&mut self, support_case: &mut SupportCase) {
@@ -156,11 +155,10 @@ impl AgentBackendDeveloper {
         );
         let msg: Message = prepare_message(&self.common.objective, &support_case.support_context, &msg_context);
 
-        let ai_response = ai_request(
-            msg,
-        )
-        .await.expect("should have returned ai response");
-        save_backend_code(&ai_response);
+        let call: LlmCall = ai_call(&self.common.role, &self.common.objective, msg).await;
+        let ai_response: Option<String> = call.response.clone();
+        support_case.trace.push(call);
+        save_backend_code(&ai_response.expect("should have returned ai response"));
     }
 
     async fn call_fix_code_bugs(&mut self, support_case: &mut SupportCase) {
@@ -171,11 +169,10 @@ impl AgentBackendDeveloper {
       );
       let msg: Message = prepare_message(&self.common.objective, &support_case.support_context, &msg_context);
 
-        let ai_response: String = ai_request(
-            msg
-        )
-        .await.expect("should have returned ai response");
-        save_backend_code(&ai_response);
+        let call: LlmCall = ai_call(&self.common.role, &self.common.objective, msg).await;
+        let ai_response: Option<String> = call.response.clone();
+        support_case.trace.push(call);
+        save_backend_code(&ai_response.expect("should have returned ai response"));
     }
 
     async fn call_extract_rest_api_endpoints(&self, support_case: &mut SupportCase) -> String {
@@ -186,11 +183,10 @@ impl AgentBackendDeveloper {
           );
           let msg: Message = prepare_message(&self.common.objective, &support_case.support_context, &msg_context);
     
-            let ai_response: String = ai_request(
-                msg
-            )
-            .await.expect("should have returned ai response");
-        ai_response
+        let call: LlmCall = ai_call(&self.common.role, &self.common.objective, msg).await;
+        let ai_response: Option<String> = call.response.clone();
+        support_case.trace.push(call);
+        ai_response.expect("should have returned ai response")
     }
 }
 
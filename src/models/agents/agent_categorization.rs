@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::{ai_case_call, extract_json};
+use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
+use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
+use crate::models::general::{llm_call::LlmCall, support_case::SupportCase};
+use crate::models::general::taxonomy::{CaseCategory, Taxonomy};
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct AgentCategorization {
+    common: CommonAgent,
+    taxonomy: Taxonomy,
+}
+
+impl AgentCategorization {
+    pub fn new(taxonomy: Taxonomy) -> Self {
+        let common = CommonAgent::new(
+            "Case Analyst".to_string(),
+            format!(
+                "You are a Case Analyst helping out Customer Support.
+            You assign incoming customer queries to one or more categories of the following taxonomy:
+            {}
+            IMPORTANT: You do not ask any follow up questions. No questions at all.
+            VERY IMPORTANT: You answer with a VALID JSON array only, most relevant category first, f.e.
+            [{{\"category\":\"billing\",\"subcategory\":\"refund\",\"confidence\":0.9}}]
+            'confidence' is a number between 0 and 1. Use null as subcategory if none fits.",
+                taxonomy.prompt_text()
+            ),
+        );
+        Self { common, taxonomy }
+    }
+
+    // Parse the model answer, keeping only categories that exist in the taxonomy
+    fn parse_categories(&self, response: &str) -> Result<Vec<CaseCategory>, serde_json::Error> {
+        let categories: Vec<CaseCategory> = serde_json::from_str(extract_json(response))?;
+        Ok(self.taxonomy.validate(categories))
+    }
+
+    async fn categorize(&mut self, support_case: &mut SupportCase) {
+        self.common.update_state(AgentState::Working);
+        let query: &str = &support_case.customer_query;
+        let mut call: LlmCall = ai_case_call(
+            &self.common.role,
+            &self.common.objective,
+            support_case.prompt_context(),
+            query,
+        )
+        .await;
+        support_case.updated();
+        match call.response.clone() {
+            Some(response) => match self.parse_categories(&response) {
+                Ok(categories) if !categories.is_empty() => {
+                    let labels: Vec<String> = categories
+                        .iter()
+                        .map(|assigned| match &assigned.subcategory {
+                            Some(subcategory) => format!(
+                                "{}/{} ({:.2})",
+                                assigned.category, subcategory, assigned.confidence
+                            ),
+                            None => format!("{} ({:.2})", assigned.category, assigned.confidence),
+                        })
+                        .collect();
+                    CLIPrint::Info.out(
+                        &self.common.role,
+                        format!("Categories: {}", labels.join(", ")).as_str(),
+                    );
+                    call.parsed = serde_json::to_value(&categories).ok();
+                    support_case.categories = categories;
+                    self.common.update_state(AgentState::Finished);
+                }
+                parsed => {
+                    call.errors.push(match parsed {
+                        Ok(_) => "No known category in answer".to_string(),
+                        Err(e) => format!("Invalid categories: {}", e),
+                    });
+                    CLIPrint::Warning.out(
+                        &self.common.role,
+                        format!("Could not categorize case from answer: {}", response).as_str(),
+                    );
+                    self.common.update_state(AgentState::Error);
+                }
+            },
+            None => {
+                CLIPrint::Warning.out(&self.common.role, call.error_text().as_str());
+                self.common.update_state(AgentState::Error);
+            }
+        }
+        support_case.trace.push(call);
+    }
+}
+
+#[async_trait]
+impl AgentFunctionTrait for AgentCategorization {
+    async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        while self.common.state != AgentState::Finished {
+            match self.common.state {
+                AgentState::Waiting => {
+                    CLIPrint::Info.out(&self.common.role, "Categorizing case...");
+                    self.categorize(support_case).await;
+                }
+                AgentState::Error => {
+                    CLIPrint::Error.out(&self.common.role, "Case stays uncategorized.");
+                    self.common.state = AgentState::Finished;
+                }
+                _ => {
+                    self.common.state = AgentState::Finished;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_common_from_agent(&self) -> &CommonAgent {
+        &self.common
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_categories() {
+        let agent: AgentCategorization = AgentCategorization::new(Taxonomy::default());
+        let response: &str = r#"```json
+            [{"category":"Billing","subcategory":"REFUND","confidence":0.92},
+             {"category":"space travel","subcategory":null,"confidence":0.5},
+             {"category":"complaint","subcategory":"noise","confidence":1.4}]
+            ```"#;
+
+        let categories: Vec<CaseCategory> = agent.parse_categories(response).unwrap();
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0].category, "billing");
+        assert_eq!(categories[0].subcategory.as_deref(), Some("refund"));
+        assert_eq!(categories[1].subcategory, None);
+        assert_eq!(categories[1].confidence, 1.0);
+        assert_eq!(
+            agent.taxonomy.escalating_category(&categories).unwrap().category,
+            "complaint"
+        );
+        assert!(agent.parse_categories("billing").is_err());
+    }
+}
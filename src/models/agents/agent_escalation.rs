@@ -1,19 +1,45 @@
 
 use async_trait::async_trait;
 
+use crate::actions::action::ActionRegistry;
 use crate::helpers::command_line::CLIPrint;
-use crate::helpers::request::{ai_request, prepare_message};
+use crate::helpers::request::{ai_call, ai_case_call_messages, extract_json, prepare_message};
+use crate::models::agents::agent_summary::AgentSummary;
 use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
 use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
 use crate::models::ai::chatgpt::Message;
-use crate::models::general::support_case::{ SupportCase };
+use crate::models::general::{
+    action::ActionResult,
+    case_status::CaseStatus,
+    escalation::{EscalationChoice, EscalationDecision, UPPER_MANAGEMENT},
+    llm_call::LlmCall,
+    notification::NotificationDelivery,
+    policy::{PolicyConfig, PolicyViolation},
+    priority::Priority,
+    support_case::SupportCase,
+    ticket::Ticket,
+};
+use crate::notifications::notifier::Notifier;
+use crate::ticketing::ticket_backend::TicketBackend;
+use serde_json::json;
 use std::error::Error;
 
 #[derive(Debug)]
 pub struct AgentEscalation {
     pub common: CommonAgent,
+    actions: ActionRegistry,
+    tickets: Box<dyn TicketBackend>,
+    notifier: Notifier,
+    summary: AgentSummary,
+    policies: PolicyConfig,
 }
 
+// Customers escalated this many times before are handed to upper management directly
+const REPEAT_ESCALATION_LIMIT: usize = 2;
+
+// How often the model is asked for a decision before upper management takes over
+const DECISION_ATTEMPTS: u32 = 2;
+
 const ACTIONS_PROMPT: &str = r#"You are in charge of customer escalations within Customer Support. 
             You handle incoming customer queries and sentiments and provide resolving actions.
             You will respond with a JSON Format of an Array of ACTIONS to call in different customer support scenarios based on context.
@@ -30,69 +56,188 @@ const ACTIONS_PROMPT: &str = r#"You are in charge of customer escalations within
 
 
 impl AgentEscalation {
-    pub fn new() -> Self {
+    pub fn new(
+        actions: ActionRegistry,
+        tickets: Box<dyn TicketBackend>,
+        notifier: Notifier,
+        policies: PolicyConfig,
+    ) -> Self {
         let common = CommonAgent::new(
             "Escalation Manager".to_string(),
             "You are an Escalation Manager helping with Customer Support. 
             You handle incoming customer queries and sentiments and decide which of the resolving actions you are provided with to call.
             If you are not sure or believe a representative of upper management should get involved because the customer really feels upset, you will answer with 'upper management'.
             IMPORTANT: You do not ask any follow up questions. No questions at all. You decide on ONE of the provided actions OR 'upper management'.
-            VERY IMPORTANT: You provide absolutely NO additional info or reasoning.".to_string(),
+            VERY IMPORTANT: Your answer is always a JSON object and nothing else, in the form
+            {\"action\": \"one of the provided actions or upper management\", \"reason\": \"one short sentence why\"}".to_string(),
         );
-        Self { common }
+        Self {
+            common,
+            actions,
+            tickets,
+            notifier,
+            summary: AgentSummary::new(),
+            policies,
+        }
     }
 
     async fn populate_resolving_actions(&mut self, support_case: &mut SupportCase){
       self.common.update_state(AgentState::Working);
-        let query: &str = &support_case.customer_query;
+        let query: &str = support_case.latest_customer_message();
         let msg: Message =
             prepare_message(ACTIONS_PROMPT, &support_case.support_context, query);
-        support_case.trace.push(msg.clone());
-        let result: Result<String, Box<dyn Error + Send>> = ai_request(msg).await;
-        if let Ok(action) = result {
-          dbg!(&action);
-          let actions:Vec<String> = serde_json::from_str(&action).expect("should be parsed into json");
-          support_case.supported_actions = actions;
-          self.common.state = AgentState::Working;
-        } else {
-          self.common.state = AgentState::Error;
+        let mut call: LlmCall = ai_call(&self.common.role, ACTIONS_PROMPT, msg).await;
+        match call
+            .response
+            .as_deref()
+            .map(|response| serde_json::from_str::<Vec<String>>(extract_json(response)))
+        {
+            Some(Ok(actions)) => {
+                call.parsed = Some(json!(actions));
+                support_case.supported_actions = actions;
+                self.common.state = AgentState::Working;
+            }
+            Some(Err(e)) => {
+                call.errors.push(format!("Invalid actions: {}", e));
+                self.common.state = AgentState::Error;
+            }
+            None => {
+                self.common.state = AgentState::Error;
+            }
         }
+        support_case.trace.push(call);
     }
 
     async fn handle_escalation(&mut self, support_case: &mut SupportCase) {
         self.common.update_state(AgentState::Working);
-        let query: &str = &support_case.customer_query;
-        let msg: Message =
-            prepare_message(format!("{} ONLY AVAILABLE ACTIONS:{}",&self.common.objective, &support_case.supported_actions.join(",")).as_str(), &support_case.support_context, query);
-        support_case.trace.push(msg.clone());
-        let result: Result<String, Box<dyn Error + Send>> = ai_request(msg).await;
-        match result {
-          Ok(action) => {
-            match action.as_str() {
-              "upper management" => {
-                support_case.needs_upper_management_attention = true;
-                support_case.escalated = true;
-              }
-              _ => {
-                support_case.support_response = Some(action);
-              }
+        // P1 cases and repeat escalations skip action selection and go straight to upper management
+        let repeat_escalations: usize = support_case
+            .customer
+            .as_ref()
+            .map(|customer| customer.recent_escalations())
+            .unwrap_or(0);
+        if support_case.priority == Priority::P1 || repeat_escalations >= REPEAT_ESCALATION_LIMIT {
+            CLIPrint::Warning.out(
+                &self.common.role,
+                format!(
+                    "{} case with {} earlier escalation(s), involving upper management.",
+                    support_case.priority, repeat_escalations
+                )
+                .as_str(),
+            );
+            support_case.escalation_decision = Some(EscalationDecision::new(
+                EscalationChoice::UpperManagement,
+                &format!(
+                    "{} case with {} earlier escalation(s)",
+                    support_case.priority, repeat_escalations
+                ),
+                "",
+                1.0,
+            ));
+            support_case.updated();
+            self.common.state = AgentState::Finished;
+            return;
+        }
+        // Earlier decisions for the customer, f.e. a discount that was already offered
+        self.common.recall(support_case);
+        let prompt_template: String = format!(
+            "{} ONLY AVAILABLE ACTIONS:{}",
+            &self.common.objective,
+            &support_case.supported_actions.join(",")
+        );
+        let mut query: String = support_case.latest_customer_message().to_string();
+        let mut decision: Option<EscalationDecision> = None;
+        for attempt in 1..=DECISION_ATTEMPTS {
+            let mut call: LlmCall = ai_case_call_messages(
+                &self.common.role,
+                &prompt_template,
+                self.common.memory.clone(),
+                support_case.prompt_context(),
+                &query,
+            )
+            .await;
+            let Some(response) = call.response.clone() else {
+                support_case.trace.push(call);
+                break;
+            };
+            match EscalationDecision::parse(&response, &support_case.supported_actions) {
+                Ok(mut parsed) => {
+                    parsed.attempts = attempt;
+                    call.parsed = Some(json!(parsed));
+                    decision = Some(parsed);
+                }
+                // Ask again, telling the model what was wrong with its answer
+                Err(e) => {
+                    CLIPrint::Warning.out(&self.common.role, e.as_str());
+                    call.errors.push(e.clone());
+                    query = format!(
+                        "{} YOUR PREVIOUS ANSWER WAS INVALID: {}. Answer with EXACTLY one of: {}, {}",
+                        support_case.latest_customer_message(),
+                        e,
+                        support_case.supported_actions.join(", "),
+                        UPPER_MANAGEMENT
+                    );
+                }
             }
-          }
-          Err(e) => {
-            self.common.state = AgentState::Error;
-          }
+            support_case.trace.push(call);
+            if decision.is_some() {
+                break;
+            }
+        }
 
+        // Without a valid choice a person has to decide
+        let mut decision: EscalationDecision = decision.unwrap_or_else(|| {
+            EscalationDecision::new(
+                EscalationChoice::UpperManagement,
+                "No valid action was chosen",
+                "",
+                0.0,
+            )
+        });
+        // Actions the policy forbids are left to upper management
+        if let EscalationChoice::Action { action } = &decision.choice {
+            let violations: Vec<PolicyViolation> = self.policies.check_action(support_case, action);
+            if let Some(violation) = violations.first() {
+                CLIPrint::Warning.out(
+                    &self.common.role,
+                    format!(
+                        "Action '{}' violates policy '{}': {}",
+                        action, violation.policy, violation.detail
+                    )
+                    .as_str(),
+                );
+                decision = EscalationDecision {
+                    choice: EscalationChoice::UpperManagement,
+                    reason: format!("Action '{}' violates policy: {}", action, violation.detail),
+                    ..decision
+                };
+            }
+        }
+        CLIPrint::Info.out(
+            &self.common.role,
+            format!("Decided on '{}': {}", decision.choice, decision.reason).as_str(),
+        );
+        if let EscalationChoice::Action { action } = &decision.choice {
+            support_case.support_response = Some(action.clone());
         }
+        let message: String = support_case.latest_customer_message().to_string();
+        self.common.remember(
+            support_case,
+            &message,
+            &format!("Decided on '{}': {}", decision.choice, decision.reason),
+        );
+        support_case.escalation_decision = Some(decision);
         support_case.updated();
         self.common.state = AgentState::Finished;
-        
     }
 }
 
 #[async_trait]
 impl AgentFunctionTrait for AgentEscalation {
     async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
-        while self.common.state != AgentState::Finished && support_case.should_escalate == true {
+        while self.common.state != AgentState::Finished
+            && support_case.status == CaseStatus::Escalated
+        {
             match self.common.state {
                 AgentState::Waiting => {
                     CLIPrint::Info.out(&self.common.role, "Preparing action items...");
@@ -118,15 +263,109 @@ impl AgentFunctionTrait for AgentEscalation {
                 }
             }
         }
-        if support_case.needs_upper_management_attention && support_case.escalated {
-          CLIPrint::Warning.out(&self.common.role, "Upper management will be with you shortly.");
-          // Do something for Upper Management.
-        } else if support_case.should_escalate {
-          CLIPrint::Info.out(&self.common.role, format!("Possible actions to choose from: {}", support_case.supported_actions.join(",")).as_str());
-
-          CLIPrint::Default.out(&self.common.role, format!("{}", support_case.support_response.as_ref().unwrap_or(&"".to_string())).as_str());
-          
-          // Create a ticket with action steps.
+        if support_case.needs_upper_management() {
+            CLIPrint::Warning.out(
+                &self.common.role,
+                "Upper management will be with you shortly.",
+            );
+        } else if support_case.status == CaseStatus::Escalated {
+            CLIPrint::Info.out(
+                &self.common.role,
+                format!(
+                    "Possible actions to choose from: {}",
+                    support_case.supported_actions.join(",")
+                )
+                .as_str(),
+            );
+
+            CLIPrint::Default.out(
+                &self.common.role,
+                format!(
+                    "{}",
+                    support_case
+                        .support_response
+                        .as_ref()
+                        .unwrap_or(&"".to_string())
+                )
+                .as_str(),
+            );
+
+            // Create a ticket with action steps.
+
+            // Carry out the chosen action
+            if let Some(EscalationDecision {
+                choice: EscalationChoice::Action { action },
+                reason,
+                ..
+            }) = support_case.escalation_decision.clone()
+            {
+                let result: ActionResult = self.actions.run(support_case, &action, &reason).await;
+                CLIPrint::Info.out(
+                    &self.common.role,
+                    format!(
+                        "Action '{}' {}: {}",
+                        result.action, result.status, result.output
+                    )
+                    .as_str(),
+                );
+            }
+        }
+
+        // Tickets and notifications carry the handoff
+        if support_case.status == CaseStatus::Escalated {
+            if let Err(e) = self.summary.execute(support_case).await {
+                CLIPrint::Warning.out(
+                    &self.common.role,
+                    format!("No handoff written: {}", e).as_str(),
+                );
+            }
+        }
+
+        // One ticket per case; later escalations of the case are tracked in the same ticket
+        if support_case.status == CaseStatus::Escalated && support_case.tickets.is_empty() {
+            match self
+                .tickets
+                .create_ticket(&Ticket::from_case(support_case))
+                .await
+            {
+                Ok(reference) => {
+                    CLIPrint::Info.out(
+                        &self.common.role,
+                        format!("Created ticket {} ({})", reference.key, reference.location)
+                            .as_str(),
+                    );
+                    support_case.tickets.push(reference);
+                    support_case.updated();
+                }
+                Err(e) => CLIPrint::Warning.out(
+                    &self.common.role,
+                    format!("No ticket created: {}", e).as_str(),
+                ),
+            }
+        }
+
+        // Notified after the ticket so the message can point to it; channels that failed are retried on the next escalation
+        if support_case.needs_upper_management() && !self.notifier.has_channels() {
+            CLIPrint::Warning.out(
+                &self.common.role,
+                "No notification channels configured, upper management was not notified.",
+            );
+        } else if support_case.needs_upper_management() && self.notifier.has_pending(support_case) {
+            let deliveries: Vec<NotificationDelivery> = self.notifier.notify(support_case).await;
+            for delivery in deliveries {
+                let detail: String = delivery
+                    .error
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default();
+                CLIPrint::Info.out(
+                    &self.common.role,
+                    format!(
+                        "Notification via {} {} after {} attempt(s){}",
+                        delivery.channel, delivery.status, delivery.attempts, detail
+                    )
+                    .as_str(),
+                );
+            }
         }
 
         Ok(())
@@ -135,4 +374,4 @@ impl AgentFunctionTrait for AgentEscalation {
     fn get_common_from_agent(&self) -> &CommonAgent {
         &self.common
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::ai_case_call;
+use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
+use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
+use crate::models::general::{intent::Intent, llm_call::LlmCall, support_case::SupportCase};
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct AgentIntent {
+    common: CommonAgent,
+}
+
+impl AgentIntent {
+    pub fn new() -> Self {
+        let common = CommonAgent::new(
+            "Intent Analyst".to_string(),
+            "You are an Intent Analyst helping out Customer Support.
+            You decide what the customer wants with their message: a question, a complaint, a cancellation, a refund request or a compliment.
+            You also extract the details the customer names, f.e. booking_id, order_id, product, room, amount or date. Only extract what is in the message.
+            IMPORTANT: You do not ask any follow up questions. No questions at all.
+            VERY IMPORTANT: Your answer is always a JSON object and nothing else, in the form
+            {\"intent\": \"question\" | \"complaint\" | \"cancellation\" | \"refund_request\" | \"compliment\",
+            \"confidence\": 0.0 to 1.0, \"slots\": {\"name\": \"value\"}}".to_string(),
+        );
+        Self { common }
+    }
+
+    async fn classify(&mut self, support_case: &mut SupportCase) {
+        self.common.update_state(AgentState::Working);
+        let query: &str = support_case.latest_customer_message();
+        let mut call: LlmCall = ai_case_call(
+            &self.common.role,
+            &self.common.objective,
+            support_case.prompt_context(),
+            query,
+        )
+        .await;
+        support_case.updated();
+        match call
+            .response
+            .as_deref()
+            .map(|response| Intent::parse(response, support_case.latest_message_index()))
+        {
+            Some(Ok(intent)) => {
+                CLIPrint::Info.out(&self.common.role, format!("Intent: {}", intent).as_str());
+                call.parsed = Some(json!(intent));
+                support_case.intent = Some(intent);
+            }
+            // Without an intent the case is answered as before
+            Some(Err(e)) => {
+                CLIPrint::Warning.out(&self.common.role, e.as_str());
+                call.errors.push(e);
+            }
+            None => {
+                CLIPrint::Warning.out(&self.common.role, call.error_text().as_str());
+            }
+        }
+        support_case.trace.push(call);
+        self.common.update_state(AgentState::Finished);
+    }
+}
+
+#[async_trait]
+impl AgentFunctionTrait for AgentIntent {
+    async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        while self.common.state != AgentState::Finished {
+            match self.common.state {
+                AgentState::Waiting => {
+                    CLIPrint::Info.out(&self.common.role, "Classifying intent...");
+                    self.classify(support_case).await;
+                }
+                _ => {
+                    self.common.state = AgentState::Finished;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_common_from_agent(&self) -> &CommonAgent {
+        &self.common
+    }
+}
@@ -1,11 +1,10 @@
 use async_trait::async_trait;
 
 use crate::helpers::command_line::CLIPrint;
-use crate::helpers::request::{ai_request, prepare_message};
+use crate::helpers::request::ai_case_call_messages;
 use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
 use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
-use crate::models::ai::chatgpt::Message;
-use crate::models::general::support_case::{self, SupportCase};
+use crate::models::general::{llm_call::LlmCall, support_case::SupportCase};
 use std::error::Error;
 
 #[derive(Debug)]
@@ -19,6 +18,7 @@ impl AgentCustomerQuery {
             "Customer Support".to_string(),
             "You are a receptionist or assistant. 
             You handle incoming customer queries and provide immediate responses before continuing to work with your customer support team.
+            You always answer in English, even when the customer writes in another language. Your answer is translated for the customer.
             IMPORTANT: You do not ask any follow up questions. No questions at all.".to_string(),
         );
         Self { common }
@@ -26,32 +26,43 @@ impl AgentCustomerQuery {
 
     async fn handle_initial_query(&mut self, support_case: &mut SupportCase) {
         self.common.update_state(AgentState::Working);
-        let query: &str = &support_case.customer_query;
-        let msg: Message =
-            prepare_message(&self.common.objective, &support_case.support_context, query);
-        support_case.trace.push(msg.clone());
-        let result: Result<String, Box<dyn Error + Send>> = ai_request(msg).await;
+        self.common.recall(support_case);
+        let query: String = support_case.latest_customer_message().to_string();
+        let call: LlmCall = ai_case_call_messages(
+            &self.common.role,
+            &self.common.objective,
+            self.common.memory.clone(),
+            support_case.prompt_context(),
+            &query,
+        )
+        .await;
         support_case.updated();
-        if let Ok(response) = result {
+        if let Some(response) = &call.response {
             support_case.support_response = Some(response.clone());
-            support_case.trace.push(Message {
-                role: "assistant".to_string(),
-                content: response,
-            });
             self.common.update_state(AgentState::Finished);
         } else {
-            support_case.support_response = Some(result.unwrap_err().to_string());
+            // The error stays in the trace; it is never sent to the customer
+            support_case.support_response = None;
             self.common.update_state(AgentState::Error);
         };
+        support_case.trace.push(call);
     }
 }
 #[async_trait]
 impl AgentFunctionTrait for AgentCustomerQuery {
     async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        // The coordinator already answered from the FAQ
+        if support_case.answered_from_faq() {
+            return Ok(());
+        }
         while self.common.state != AgentState::Finished {
             match self.common.state {
                 AgentState::Waiting => {
-                    CLIPrint::Info.out(&self.common.role, format!("Handling initial query: {}", &support_case.customer_query).as_str());
+                    CLIPrint::Info.out(
+                        &self.common.role,
+                        format!("Handling query: {}", support_case.latest_customer_message())
+                            .as_str(),
+                    );
                     self.handle_initial_query(support_case).await;
                 }
                 AgentState::Error => {
@@ -59,7 +70,7 @@ impl AgentFunctionTrait for AgentCustomerQuery {
                         &self.common.role,
                         format!(
                             "There was an error: {:?}",
-                            Some(&support_case.support_response)
+                            support_case.trace.last().map(|call| call.error_text())
                         )
                         .as_str(),
                     );
@@ -71,11 +82,6 @@ impl AgentFunctionTrait for AgentCustomerQuery {
             }
         }
 
-        CLIPrint::Default.out(
-            &self.common.role,
-            format!("{}", support_case.support_response.as_ref().unwrap()).as_str(),
-        );
-
         Ok(())
     }
 
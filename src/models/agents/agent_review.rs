@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::ai_case_call;
+use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
+use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
+use crate::models::general::{
+    case_status::CaseStatus,
+    llm_call::LlmCall,
+    review::{ResponseReview, ReviewConfig, ReviewVerdict},
+    support_case::{PromptContext, SupportCase},
+};
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct AgentReview {
+    common: CommonAgent,
+    config: ReviewConfig,
+}
+
+impl AgentReview {
+    pub fn new(config: ReviewConfig) -> Self {
+        let common = CommonAgent::new(
+            "Quality Reviewer".to_string(),
+            "You are a Quality Reviewer in Customer Support. You check the draft reply in the QUERY before it is sent to the customer.
+            You check the tone against the TONE GUIDELINES, the content against the POLICY CONSTRAINTS,
+            and that every fact in the draft is grounded in the CONTEXT: the customer's message, what we know about the customer and the attachments.
+            If the draft is fine you approve it. If it can be fixed you rewrite it in English, keeping everything that is fine.
+            If it cannot be sent at all, f.e. because it would need facts we do not have, you block it.
+            IMPORTANT: You do not ask any follow up questions. No questions at all.
+            VERY IMPORTANT: Your answer is always a JSON object and nothing else, in the form
+            {\"verdict\": \"approve\" | \"rewrite\" | \"block\", \"issues\": [\"problems found in the draft\"],
+            \"rewritten\": \"the new reply, only for rewrite\", \"reason\": \"one short sentence\"}".to_string(),
+        );
+        Self { common, config }
+    }
+
+    async fn review_response(&mut self, support_case: &mut SupportCase, draft: String) {
+        self.common.update_state(AgentState::Working);
+        let prompt_template: String =
+            format!("{} {}", self.common.objective, self.config.prompt_text());
+        let mut context: PromptContext = support_case.prompt_context();
+        context.notes.push(format!(
+            "CUSTOMER MESSAGE: {}",
+            support_case.latest_customer_message()
+        ));
+        let mut call: LlmCall =
+            ai_case_call(&self.common.role, &prompt_template, context, &draft).await;
+        support_case.updated();
+        let review: ResponseReview = match call.response.as_deref().map(ResponseReview::parse) {
+            Some(Ok(review)) => review,
+            // An unreviewed draft is held like a blocked one; the trace shows why
+            Some(Err(e)) => {
+                call.errors.push(e.clone());
+                support_case.trace.push(call);
+                self.hold_reply(support_case, format!("Reply could not be reviewed: {}", e));
+                self.common.update_state(AgentState::Finished);
+                return;
+            }
+            None => {
+                let reason: String = format!("Reply could not be reviewed: {}", call.error_text());
+                support_case.trace.push(call);
+                self.hold_reply(support_case, reason);
+                self.common.update_state(AgentState::Finished);
+                return;
+            }
+        };
+
+        let reply: String = match review.verdict {
+            ReviewVerdict::Approve => {
+                CLIPrint::Info.out(&self.common.role, "Reply approved.");
+                draft.clone()
+            }
+            ReviewVerdict::Rewrite => {
+                let rewritten: String = review.rewritten.clone().unwrap_or(draft.clone());
+                CLIPrint::Info.out(
+                    &self.common.role,
+                    format!(
+                        "Reply rewritten ({}): {}",
+                        review.issues.join("; "),
+                        rewritten
+                    )
+                    .as_str(),
+                );
+                rewritten
+            }
+            ReviewVerdict::Block => {
+                self.hold_reply(
+                    support_case,
+                    format!("Review blocked the reply: {}", review.reason),
+                );
+                self.config.blocked_reply.clone()
+            }
+        };
+        call.parsed = Some(json!({
+            "verdict": review.verdict,
+            "issues": review.issues,
+            "reason": review.reason,
+            "draft": draft,
+            "reply": reply,
+        }));
+        support_case.support_response = Some(reply);
+        support_case.trace.push(call);
+        self.common.update_state(AgentState::Finished);
+    }
+
+    // The customer gets the blocked reply and a person looks at the case
+    fn hold_reply(&self, support_case: &mut SupportCase, reason: String) {
+        CLIPrint::Warning.out(&self.common.role, reason.as_str());
+        support_case.support_response = Some(self.config.blocked_reply.clone());
+        if support_case.status != CaseStatus::Escalated {
+            if let Err(e) =
+                support_case.transition(CaseStatus::Escalated, &self.common.role, &reason)
+            {
+                CLIPrint::Warning.out(&self.common.role, e.to_string().as_str());
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AgentFunctionTrait for AgentReview {
+    async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        // Only replies that have not been reviewed and translated yet; FAQ answers are vetted
+        let draft: String = match &support_case.support_response {
+            Some(draft)
+                if support_case.original_response.is_none()
+                    && !support_case.answered_from_faq() =>
+            {
+                draft.clone()
+            }
+            _ => return Ok(()),
+        };
+        while self.common.state != AgentState::Finished {
+            match self.common.state {
+                AgentState::Waiting => {
+                    CLIPrint::Info.out(&self.common.role, "Reviewing reply...");
+                    self.review_response(support_case, draft.clone()).await;
+                }
+                _ => {
+                    self.common.state = AgentState::Finished;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_common_from_agent(&self) -> &CommonAgent {
+        &self.common
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_review_holds_reply() {
+        let agent: AgentReview = AgentReview::new(ReviewConfig::default());
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case
+            .transition(
+                CaseStatus::InProgress,
+                "Coordinator",
+                "Support request received",
+            )
+            .unwrap();
+        support_case.support_response = Some("We will give you a free suite.".to_string());
+
+        agent.hold_reply(
+            &mut support_case,
+            "Reply could not be reviewed: timeout".to_string(),
+        );
+        assert_eq!(
+            support_case.support_response,
+            Some(ReviewConfig::default().blocked_reply)
+        );
+        assert_eq!(support_case.status, CaseStatus::Escalated);
+    }
+}
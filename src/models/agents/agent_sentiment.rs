@@ -1,61 +1,81 @@
 use async_trait::async_trait;
+use chrono::Local;
 
 use crate::helpers::command_line::CLIPrint;
-use crate::helpers::request::{ai_request, prepare_message};
+use crate::helpers::request::ai_case_call;
 use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
 use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
-use crate::models::ai::chatgpt::Message;
-use crate::models::general::support_case::{self, SupportCase};
+use crate::models::general::{
+    llm_call::LlmCall,
+    sentiment::{Sentiment, SentimentConfig, SentimentPoint},
+    support_case::SupportCase,
+};
+use serde_json::json;
 use std::error::Error;
 
 #[derive(Debug)]
 pub struct AgentSentiment {
     common: CommonAgent,
+    config: SentimentConfig,
 }
 
 impl AgentSentiment {
-    pub fn new() -> Self {
+    pub fn new(config: SentimentConfig) -> Self {
         let common = CommonAgent::new(
             "Psychologist".to_string(),
             "You are a Psychologist helping out Customer Support. 
-            You handle incoming customer queries analyze them and their sentiment into one of the following categories: 'Negative', 'Positive'.
+            You handle incoming customer queries and analyze their sentiment.
             IMPORTANT: You do not ask any follow up questions. No questions at all.
-            VERY IMPORTANT: Your answer is always either 'Positive' or 'Negative'. You provide absolutely NO additional info.".to_string(),
+            VERY IMPORTANT: Your answer is always a JSON object and nothing else, in the form
+            {\"polarity\": \"positive\" | \"neutral\" | \"mixed\" | \"negative\", \"intensity\": 0.0 to 1.0,
+            \"emotions\": any of [\"anger\", \"frustration\", \"confusion\"], \"confidence\": 0.0 to 1.0,
+            \"rationale\": \"one short sentence\"}".to_string(),
         );
-        Self { common }
+        Self { common, config }
     }
 
     async fn analyze_sentiment(&mut self, support_case: &mut SupportCase) {
         self.common.update_state(AgentState::Working);
-        let query: &str = &support_case.customer_query;
-        let msg: Message =
-            prepare_message(&self.common.objective, &support_case.support_context, query);
-        support_case.trace.push(msg.clone());
-        let result: Result<String, Box<dyn Error + Send>> = ai_request(msg).await;
+        let query: &str = support_case.latest_customer_message();
+        let mut call: LlmCall = ai_case_call(
+            &self.common.role,
+            &self.common.objective,
+            support_case.prompt_context(),
+            query,
+        )
+        .await;
         support_case.updated();
-        if let Ok(response) = result {
-            support_case.sentiment = Some(response.clone());
-            match response.as_str() {
-                "Positive" => {
-                    CLIPrint::Info.out(
-                        &self.common.role,
-                        "Sentiment is 'Positive'",
-                    );
+        if let Some(response) = call.response.clone() {
+            match Sentiment::parse(&response) {
+                // The coordinator decides on escalation from the whole trajectory
+                Ok(sentiment) => {
+                    call.parsed = Some(json!(sentiment));
+                    let message: String = format!("Sentiment is '{}'", sentiment);
+                    if sentiment.should_escalate(&self.config) {
+                        CLIPrint::Warning.out(&self.common.role, message.as_str());
+                    } else {
+                        CLIPrint::Info.out(&self.common.role, message.as_str());
+                    }
+                    support_case.sentiment_trajectory.push(SentimentPoint {
+                        message_index: support_case.latest_message_index(),
+                        at: Local::now(),
+                        sentiment: sentiment.clone(),
+                    });
+                    support_case.sentiment = Some(sentiment);
+                    self.common.update_state(AgentState::Finished);
                 }
-                "Negative" => {
-                    CLIPrint::Warning.out(&self.common.role, "Sentiment is 'Negative'");
-                    support_case.should_escalate = true;
-                }
-                _ => {
-                    self.common.update_state(AgentState::Error);
+                // An unreadable answer leaves the sentiment unknown instead of failing the case
+                Err(e) => {
+                    CLIPrint::Warning.out(&self.common.role, e.as_str());
+                    call.errors.push(e);
+                    self.common.update_state(AgentState::Finished);
                 }
             }
-
-            self.common.update_state(AgentState::Finished);
         } else {
-            support_case.support_response = Some(result.unwrap_err().to_string());
+            support_case.support_response = Some(call.error_text());
             self.common.update_state(AgentState::Error);
         };
+        support_case.trace.push(call);
     }
 }
 
@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::ai_case_call;
+use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
+use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
+use crate::models::general::{
+    case_status::CaseStatus,
+    handoff::Handoff,
+    llm_call::LlmCall,
+    support_case::{PromptContext, SupportCase},
+    ticket::{transcript, TranscriptRole},
+};
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct AgentSummary {
+    common: CommonAgent,
+}
+
+// Escalation details the handoff is written from, next to the transcript
+fn escalation_context(support_case: &SupportCase) -> PromptContext {
+    let mut context: PromptContext = support_case.prompt_context();
+    if let Some(sentiment) = &support_case.sentiment {
+        let trajectory: Vec<String> = support_case
+            .sentiment_trajectory
+            .iter()
+            .map(|point| format!("#{} {}", point.message_index, point.sentiment))
+            .collect();
+        context.notes.push(format!(
+            "SENTIMENT: {} (TRAJECTORY: {})",
+            sentiment,
+            trajectory.join(", ")
+        ));
+    }
+    if !support_case.supported_actions.is_empty() {
+        context.notes.push(format!(
+            "AVAILABLE ACTIONS: {}",
+            support_case.supported_actions.join(", ")
+        ));
+    }
+    if let Some(decision) = &support_case.escalation_decision {
+        context.notes.push(format!(
+            "DECISION: {} ({})",
+            decision.choice, decision.reason
+        ));
+    }
+    // With their output, the handoff says what each action did
+    context.actions = support_case
+        .action_results
+        .iter()
+        .map(|result| format!("{} {}: {}", result.action, result.status, result.output))
+        .collect();
+    context
+}
+
+impl AgentSummary {
+    pub fn new() -> Self {
+        let common = CommonAgent::new(
+            "Handoff Writer".to_string(),
+            "You are a Team Lead in Customer Support handing an escalated case over to a colleague.
+            You read the conversation in the QUERY and the case details in the CONTEXT and write a concise handoff.
+            IMPORTANT: You do not ask any follow up questions. Only state what is in the conversation and the context, never make anything up.
+            VERY IMPORTANT: Your answer is always a JSON object and nothing else, in the form
+            {\"issue\": \"the customer's issue in one or two sentences\", \"already_said\": [\"short points of what customer and support already said\"],
+            \"sentiment\": \"how the customer feels\", \"actions_considered\": [\"actions that were considered or taken\"],
+            \"next_step\": \"the one next step you recommend\", \"open_questions\": [\"what is still unclear\"]}".to_string(),
+        );
+        Self { common }
+    }
+
+    async fn summarize(&mut self, support_case: &mut SupportCase) {
+        self.common.update_state(AgentState::Working);
+        let conversation: Vec<String> = transcript(support_case)
+            .iter()
+            .map(|entry| {
+                let role: &str = match entry.role {
+                    TranscriptRole::Customer => "CUSTOMER",
+                    TranscriptRole::Support => "SUPPORT",
+                };
+                format!("{}: {}", role, entry.text)
+            })
+            .collect();
+        let mut call: LlmCall = ai_case_call(
+            &self.common.role,
+            &self.common.objective,
+            escalation_context(support_case),
+            &conversation.join("\n"),
+        )
+        .await;
+        let handoff: Handoff = match call.response.as_deref().map(Handoff::parse) {
+            Some(Ok(handoff)) => {
+                call.parsed = Some(json!(handoff));
+                handoff
+            }
+            // People still get a handoff, built from what the case records
+            Some(Err(e)) => {
+                CLIPrint::Warning.out(&self.common.role, e.as_str());
+                call.errors.push(e);
+                Handoff::from_case(support_case)
+            }
+            None => {
+                CLIPrint::Warning.out(&self.common.role, call.error_text().as_str());
+                Handoff::from_case(support_case)
+            }
+        };
+        CLIPrint::Info.out(
+            &self.common.role,
+            format!("Recommended next step: {}", handoff.next_step).as_str(),
+        );
+        support_case.handoff = Some(handoff);
+        support_case.trace.push(call);
+        support_case.updated();
+        self.common.update_state(AgentState::Finished);
+    }
+}
+
+#[async_trait]
+impl AgentFunctionTrait for AgentSummary {
+    async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        // Only escalated cases are handed over to people
+        if support_case.status != CaseStatus::Escalated {
+            return Ok(());
+        }
+        while self.common.state != AgentState::Finished {
+            match self.common.state {
+                AgentState::Waiting => {
+                    CLIPrint::Info.out(&self.common.role, "Writing handoff...");
+                    self.summarize(support_case).await;
+                }
+                _ => {
+                    self.common.state = AgentState::Finished;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_common_from_agent(&self) -> &CommonAgent {
+        &self.common
+    }
+}
@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::{ai_call, prepare_message};
+use crate::models::agents_common::common_agent::{AgentState, CommonAgent};
+use crate::models::agents_common::common_traits::{AgentFunctionTrait, CommonTrait};
+use crate::models::ai::chatgpt::Message;
+use crate::models::general::{
+    language::CaseLanguage, llm_call::LlmCall, support_case::SupportCase,
+};
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct AgentTranslation {
+    common: CommonAgent,
+}
+
+impl AgentTranslation {
+    pub fn new() -> Self {
+        let common = CommonAgent::new(
+            "Translator".to_string(),
+            "You are a professional translator working for Customer Support.
+            You translate the QUERY, a reply written by Customer Support, into the TARGET LANGUAGE given in the CONTEXT.
+            Keep the meaning, the tone, names, numbers and any formatting.
+            IMPORTANT: You do not ask any follow up questions. No questions at all.
+            VERY IMPORTANT: You answer with the translated text only. You provide absolutely NO additional info.".to_string(),
+        );
+        Self { common }
+    }
+
+    async fn translate_response(&mut self, support_case: &mut SupportCase, language: &CaseLanguage) {
+        self.common.update_state(AgentState::Working);
+        let Some(original) = support_case.support_response.clone() else {
+            self.common.update_state(AgentState::Finished);
+            return;
+        };
+        let context: String = format!("TARGET LANGUAGE: {} ({})", language.name, language.code);
+        let msg: Message = prepare_message(&self.common.objective, &context, &original);
+        let mut call: LlmCall = ai_call(&self.common.role, &self.common.objective, msg).await;
+        support_case.updated();
+        match call.response.clone() {
+            Some(translated) => {
+                call.parsed = Some(json!({
+                    "language": language.code,
+                    "original": original,
+                    "translated": translated,
+                }));
+                support_case.original_response = Some(original);
+                support_case.support_response = Some(translated);
+                self.common.update_state(AgentState::Finished);
+            }
+            None => {
+                CLIPrint::Warning.out(&self.common.role, call.error_text().as_str());
+                self.common.update_state(AgentState::Error);
+            }
+        }
+        support_case.trace.push(call);
+    }
+}
+
+#[async_trait]
+impl AgentFunctionTrait for AgentTranslation {
+    async fn execute(&mut self, support_case: &mut SupportCase) -> Result<(), Box<dyn Error>> {
+        // Replies are written in English; only other languages and untranslated replies need work
+        let language: CaseLanguage = match &support_case.language {
+            Some(language) if !language.is_english() && support_case.original_response.is_none() => {
+                language.clone()
+            }
+            _ => return Ok(()),
+        };
+        while self.common.state != AgentState::Finished {
+            match self.common.state {
+                AgentState::Waiting => {
+                    CLIPrint::Info.out(
+                        &self.common.role,
+                        format!("Translating reply to {}...", language.name).as_str(),
+                    );
+                    self.translate_response(support_case, &language).await;
+                }
+                AgentState::Error => {
+                    CLIPrint::Error.out(&self.common.role, "Reply stays in English.");
+                    self.common.state = AgentState::Finished;
+                }
+                _ => {
+                    self.common.state = AgentState::Finished;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_common_from_agent(&self) -> &CommonAgent {
+        &self.common
+    }
+}
@@ -1,4 +1,9 @@
+pub mod agent_categorization;
 pub mod agent_escalation;
+pub mod agent_intent;
 pub mod agent_query;
+pub mod agent_review;
 pub mod agent_sentiment;
+pub mod agent_summary;
+pub mod agent_translation;
 pub mod agent_backend;
\ No newline at end of file
@@ -1,4 +1,5 @@
 use crate::models::ai::chatgpt::Message;
+use crate::models::general::support_case::SupportCase;
 
 use super::common_traits::CommonTrait;
 
@@ -16,9 +17,28 @@ pub struct CommonAgent {
     pub role: String,
     pub objective: String,
     pub state: AgentState,
+    // What the agent remembers of the case, loaded with 'recall'
     pub memory: Vec<Message>,
 }
 
+impl CommonAgent {
+    // Loads the agent's memory of the case, including what was carried over from earlier cases
+    pub fn recall(&mut self, support_case: &SupportCase) {
+        self.memory = support_case
+            .memory_for(&self.role)
+            .map(|memory| memory.messages())
+            .unwrap_or_default();
+    }
+
+    // Stores an exchange in the agent's memory of the case
+    pub fn remember(&mut self, support_case: &mut SupportCase, message: &str, answer: &str) {
+        let memory = support_case.memory_mut(&self.role);
+        memory.remember("user", message);
+        memory.remember("assistant", answer);
+        self.recall(support_case);
+    }
+}
+
 impl CommonTrait for CommonAgent {
     fn new(role: String, objective: String) -> Self {
         Self {
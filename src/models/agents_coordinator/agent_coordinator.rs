@@ -1,24 +1,299 @@
+use chrono::{Duration, Local};
+use std::error::Error;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::actions::action::ActionRegistry;
+use crate::api::embedding_request::call_embedding;
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::request::ai_call;
 use crate::models::agents::agent_backend::AgentBackendDeveloper;
+use crate::models::agents::agent_categorization::AgentCategorization;
 use crate::models::agents::agent_escalation::AgentEscalation;
+use crate::models::agents::agent_intent::AgentIntent;
+use crate::models::agents::agent_review::AgentReview;
+use crate::models::agents::agent_translation::AgentTranslation;
 use crate::models::agents::{agent_query::AgentCustomerQuery, agent_sentiment::AgentSentiment};
 use crate::models::agents_common::{
     common_agent::CommonAgent,
     common_traits::{AgentFunctionTrait, CommonTrait},
 };
-use crate::models::general::support_case::SupportCase;
+use crate::models::ai::chatgpt::Message;
+use crate::models::general::{
+    action::{ActionConfig, ActionResult},
+    attachment::AttachmentConfig,
+    case_status::CaseStatus,
+    customer::Customer,
+    faq::{FaqHit, FaqStore},
+    intent::{IntentConfig, IntentRoute},
+    language::{detect_language, CaseLanguage},
+    llm_call::LlmCall,
+    memory::{AgentMemory, MemoryConfig},
+    notification::NotificationConfig,
+    policy::{PolicyConfig, PolicyViolation},
+    priority::PriorityConfig,
+    review::ReviewConfig,
+    sentiment::{SentimentConfig, SentimentTrend, TrendDecision},
+    similarity::{CaseLink, CaseRelation, SimilarityConfig},
+    sla::{SlaConfig, SlaEvaluation},
+    support_case::SupportCase,
+    taxonomy::{CaseCategory, Taxonomy},
+    ticket::TicketConfig,
+};
+use crate::notifications::notifier::Notifier;
+use crate::storage::{
+    attachment_store::AttachmentStore,
+    audit_log::record_case,
+    case_store::{CaseFilter, CaseStore},
+};
+use crate::ticketing::ticket_backend::ticket_backend_from_config;
+
+// How many of the customer's previous cases are shown to agents
+const RECENT_CASE_LIMIT: usize = 5;
+
+// Reason of escalations made for the customer's sentiment; only these are taken back on recovery
+const SENTIMENT_ESCALATION_REASON: &str = "Customer sentiment";
 
 pub struct CoordinatorAgent {
     support_case: SupportCase,
     agents: Vec<Box<dyn AgentFunctionTrait>>,
+    store: Box<dyn CaseStore>,
+    priority_config: PriorityConfig,
+    sla_config: SlaConfig,
+    taxonomy: Taxonomy,
+    similarity_config: SimilarityConfig,
+    sentiment_config: SentimentConfig,
+    policy_config: PolicyConfig,
+    intent_config: IntentConfig,
+    faq: FaqStore,
+    memory_config: MemoryConfig,
+    translate_responses: bool,
+}
+
+// Persistence failures are reported but never interrupt the support request
+fn persist_case(store: &mut dyn CaseStore, support_case: &SupportCase) {
+    if let Err(e) = record_case(store, support_case, "Coordinator") {
+        CLIPrint::Error.out(
+            "Coordinator",
+            format!("Failed to persist case {}: {}", support_case.case_id, e).as_str(),
+        );
+    }
+}
+
+// Keeps priority, first response time and SLA state in line with what the agents did
+fn track_case(
+    support_case: &mut SupportCase,
+    priority_config: &PriorityConfig,
+    sla_config: &SlaConfig,
+) -> SlaEvaluation {
+    support_case.reprioritize(priority_config);
+    if support_case.support_response.is_some() && support_case.first_response_at.is_none() {
+        support_case.first_response_at = Some(Local::now());
+    }
+    support_case.refresh_sla(sla_config, Local::now())
+}
+
+// Escalates or takes back a sentiment escalation based on the sentiment trajectory
+fn follow_sentiment(support_case: &mut SupportCase, config: &SentimentConfig) {
+    let Some(decision) = config.decide(&support_case.sentiment_trajectory) else {
+        return;
+    };
+    let Some(sentiment) = support_case.sentiment.clone() else {
+        return;
+    };
+    let trend: SentimentTrend = config.trend(&support_case.sentiment_trajectory);
+    let escalated_for_sentiment: bool = support_case
+        .status_history
+        .iter()
+        .rev()
+        .find(|transition| transition.to == CaseStatus::Escalated)
+        .is_some_and(|transition| transition.reason.starts_with(SENTIMENT_ESCALATION_REASON));
+    let (to, reason): (CaseStatus, String) = match decision {
+        TrendDecision::Escalate if support_case.status != CaseStatus::Escalated => (
+            CaseStatus::Escalated,
+            format!(
+                "{} {}, trend {}",
+                SENTIMENT_ESCALATION_REASON, sentiment, trend
+            ),
+        ),
+        TrendDecision::CancelEscalation
+            if support_case.status == CaseStatus::Escalated && escalated_for_sentiment =>
+        {
+            (
+                CaseStatus::InProgress,
+                format!("Customer recovered to {}, trend {}", sentiment, trend),
+            )
+        }
+        _ => return,
+    };
+    CLIPrint::Info.out("Coordinator", reason.as_str());
+    if let Err(e) = support_case.transition(to, "Coordinator", &reason) {
+        CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+    }
+}
+
+// Checks a new reply against the policy; blocked replies escalate the case
+fn guard_reply(
+    support_case: &mut SupportCase,
+    config: &PolicyConfig,
+    checked: &mut Option<String>,
+) {
+    if support_case.support_response == *checked {
+        return;
+    }
+    let violations: Vec<PolicyViolation> = config.enforce_reply(support_case);
+    for violation in &violations {
+        CLIPrint::Warning.out(
+            "Policy",
+            format!(
+                "Reply {} by policy '{}' ({}): {}",
+                violation.outcome, violation.policy, violation.kind, violation.detail
+            )
+            .as_str(),
+        );
+    }
+    *checked = support_case.support_response.clone();
+}
+
+// Answers the latest message with the canonical FAQ answer when it matches a known question
+fn answer_from_faq(support_case: &mut SupportCase, faq: &FaqStore) -> bool {
+    let Some((entry, question, score)) = faq.find(
+        &support_case.support_context,
+        support_case.latest_customer_message(),
+    ) else {
+        return false;
+    };
+    CLIPrint::Info.out(
+        "Coordinator",
+        format!(
+            "Answering from FAQ '{}' ({:.2}): {}",
+            entry.id, score, question
+        )
+        .as_str(),
+    );
+    let hit: FaqHit = FaqHit {
+        faq_id: entry.id.clone(),
+        question: question.to_string(),
+        score,
+        message_index: support_case.latest_message_index(),
+        at: Local::now(),
+    };
+    support_case.support_response = Some(entry.answer.clone());
+    support_case.original_response = None;
+    support_case.faq_hits.push(hit);
+    support_case.updated();
+    true
+}
+
+// Summarizes the entries agents can no longer keep in memory; without the model the start of each entry is kept
+async fn compact_memory(support_case: &mut SupportCase, config: &MemoryConfig) {
+    for index in 0..support_case.agent_memory.len() {
+        let old_entries: Vec<Message> = support_case.agent_memory[index].overflow(config);
+        if old_entries.is_empty() {
+            continue;
+        }
+        let memory: &AgentMemory = &support_case.agent_memory[index];
+        let role: String = memory.role.clone();
+        let prompt_template: String = format!(
+            "You are the Memory Keeper of the {}. Summarize what the customer said and what was answered or decided, \
+            f.e. offers, discounts or actions that were already taken, so they are not repeated. \
+            Keep names, numbers and dates. Answer with the summary only, in at most {} characters.",
+            role, config.max_summary_chars
+        );
+        let lines: String = old_entries
+            .iter()
+            .map(|entry| format!("{}: {}", entry.role, entry.content))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let msg: Message = Message {
+            role: "system".to_string(),
+            content: format!(
+                "{} EARLIER SUMMARY: {} ENTRIES: {}",
+                prompt_template,
+                memory.summary.as_deref().unwrap_or("None"),
+                lines
+            ),
+        };
+        let fallback: String = memory.fallback_summary(&old_entries);
+        let call: LlmCall = ai_call("Memory Keeper", &prompt_template, msg).await;
+        let summary: String = match call.response.as_deref().map(str::trim) {
+            Some(summary) if !summary.is_empty() => summary.to_string(),
+            _ => {
+                CLIPrint::Warning.out(
+                    "Coordinator",
+                    format!(
+                        "Folding memory of {} without summary: {}",
+                        role,
+                        call.error_text()
+                    )
+                    .as_str(),
+                );
+                fallback
+            }
+        };
+        support_case.trace.push(call);
+        support_case.agent_memory[index].fold(&summary, config);
+        CLIPrint::Info.out(
+            "Coordinator",
+            format!(
+                "Summarized {} old memory entries of {}",
+                old_entries.len(),
+                role
+            )
+            .as_str(),
+        );
+        support_case.updated();
+    }
+}
+
+// Categories routed to escalation send the case to the escalation agent right away
+fn route_case(support_case: &mut SupportCase, taxonomy: &Taxonomy) {
+    if support_case.status == CaseStatus::Escalated {
+        return;
+    }
+    let Some(category) = taxonomy.escalating_category(&support_case.categories) else {
+        return;
+    };
+    let CaseCategory { category, confidence, .. } = category.clone();
+    CLIPrint::Info.out(
+        "Coordinator",
+        format!("Routing '{}' case to escalation", category).as_str(),
+    );
+    let reason: String = format!("Category '{}' (confidence {:.2})", category, confidence);
+    if let Err(e) = support_case.transition(CaseStatus::Escalated, "Coordinator", &reason) {
+        CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+    }
 }
 
 impl CoordinatorAgent {
-    pub fn new(context: String, query: String) -> Self {
-        let support_case = SupportCase::new(context, query);
-    
+    pub fn new(
+        context: String,
+        query: String,
+        customer_id: Option<Uuid>,
+        store: Box<dyn CaseStore>,
+    ) -> Self {
+        let mut support_case = SupportCase::new(context, query);
+        support_case.customer_id = customer_id;
+        Self::from_case(support_case, store)
+    }
+    // Coordinator for a stored case, f.e. to handle a customer reply
+    pub fn from_case(support_case: SupportCase, store: Box<dyn CaseStore>) -> Self {
         Self {
             support_case,
             agents: vec![],
+            store,
+            priority_config: PriorityConfig::load(),
+            sla_config: SlaConfig::load(),
+            taxonomy: Taxonomy::load(),
+            similarity_config: SimilarityConfig::load(),
+            sentiment_config: SentimentConfig::load(),
+            policy_config: PolicyConfig::load(),
+            intent_config: IntentConfig::load(),
+            faq: FaqStore::load(),
+            memory_config: MemoryConfig::load(),
+            translate_responses: dotenv::var("TRANSLATE_RESPONSES")
+                .map(|value| value != "false")
+                .unwrap_or(true),
         }
     }
     fn add_agent(&mut self, agent: Box<dyn AgentFunctionTrait>) {
@@ -26,18 +301,403 @@ impl CoordinatorAgent {
     }
     fn create_agents(&mut self) {
         self.add_agent(Box::new(AgentCustomerQuery::new()));
-        self.add_agent(Box::new(AgentSentiment::new()));
-        self.add_agent(Box::new(AgentEscalation::new()));
+        self.add_agent(Box::new(AgentReview::new(ReviewConfig::load())));
+        self.add_agent(Box::new(AgentSentiment::new(self.sentiment_config.clone())));
+        self.add_agent(Box::new(AgentEscalation::new(
+            ActionRegistry::from_config(&ActionConfig::load()),
+            ticket_backend_from_config(&TicketConfig::load().backend),
+            Notifier::from_config(NotificationConfig::load()),
+            self.policy_config.clone(),
+        )));
         self.add_agent(Box::new(AgentBackendDeveloper::new()));
     }
+    // Status changes requested by the coordinator itself
+    fn request_transition(&mut self, to: CaseStatus, reason: &str) {
+        if let Err(e) = self.support_case.transition(to, "Coordinator", reason) {
+            CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+        }
+    }
+    // Snapshot the customer's profile and latest cases so agents can take them into account
+    fn attach_customer(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(customer_id) = self.support_case.customer_id else {
+            return Ok(());
+        };
+        let customer: Customer = self
+            .store
+            .get_customer(&customer_id)?
+            .ok_or_else(|| format!("Customer {} not found", customer_id))?;
+        let mut recent_cases: Vec<SupportCase> = self.store.list(&CaseFilter {
+            customer_id: Some(customer_id),
+            ..Default::default()
+        })?;
+        recent_cases.retain(|support_case| support_case.case_id != self.support_case.case_id);
+        recent_cases.reverse();
+        recent_cases.truncate(RECENT_CASE_LIMIT);
+
+        // Agents pick up where they left off in the customer's latest case
+        if self.memory_config.carry_over && self.support_case.agent_memory.is_empty() {
+            if let Some(previous) = recent_cases
+                .iter()
+                .find(|support_case| !support_case.agent_memory.is_empty())
+            {
+                self.support_case.agent_memory = previous
+                    .agent_memory
+                    .iter()
+                    .cloned()
+                    .map(|mut memory| {
+                        memory.carried_from = Some(previous.case_id);
+                        memory
+                    })
+                    .collect();
+                CLIPrint::Info.out(
+                    "Coordinator",
+                    format!("Carried agent memory over from case {}", previous.case_id).as_str(),
+                );
+            }
+        }
+
+        self.support_case.attach_customer(&customer, &recent_cases);
+        Ok(())
+    }
+    // Store files sent with the request; rejected files are reported and skipped
+    pub fn attach_files(&mut self, paths: &[String]) {
+        let attachment_store: AttachmentStore = AttachmentStore::new(AttachmentConfig::load());
+        for path in paths {
+            match attachment_store.save(&self.support_case.case_id, Path::new(path)) {
+                Ok(attachment) => {
+                    CLIPrint::Info.out(
+                        "Coordinator",
+                        format!(
+                            "Attached {} ({}, {} bytes)",
+                            attachment.file_name, attachment.mime_type, attachment.size_bytes
+                        )
+                        .as_str(),
+                    );
+                    self.support_case.attachments.push(attachment);
+                }
+                Err(e) => CLIPrint::Warning.out("Coordinator", e.to_string().as_str()),
+            }
+        }
+    }
+    // Link the case and similar stored cases to each other and return the best duplicate
+    async fn link_similar_cases(&mut self) -> Option<SupportCase> {
+        if self.similarity_config.use_embeddings {
+            match call_embedding(&self.support_case.customer_query).await {
+                Ok(embedding) => self.support_case.embedding = Some(embedding),
+                Err(e) => {
+                    CLIPrint::Warning.out("Coordinator", format!("No embedding: {}", e).as_str())
+                }
+            }
+        }
+        let candidates: Vec<SupportCase> = match self.store.list(&CaseFilter {
+            created_from: Some(
+                self.support_case.created_at - Duration::days(self.similarity_config.window_days),
+            ),
+            ..Default::default()
+        }) {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                CLIPrint::Warning.out("Coordinator", format!("No similar cases: {}", e).as_str());
+                return None;
+            }
+        };
+
+        let mut duplicate: Option<SupportCase> = None;
+        for link in self.similarity_config.find_similar(&self.support_case, &candidates) {
+            let Some(mut other) = candidates
+                .iter()
+                .find(|candidate| candidate.case_id == link.case_id)
+                .cloned()
+            else {
+                continue;
+            };
+            CLIPrint::Info.out(
+                "Coordinator",
+                format!(
+                    "Found {} case {} (score {:.2})",
+                    link.relation, other.case_id, link.score
+                )
+                .as_str(),
+            );
+            other.link_case(CaseLink {
+                case_id: self.support_case.case_id,
+                ..link.clone()
+            });
+            persist_case(self.store.as_mut(), &other);
+            // Only the customer's own open cases can absorb the new one; any resolution can be reused
+            let same_customer: bool = self.support_case.customer_id.is_some()
+                && other.customer_id == self.support_case.customer_id;
+            let resolved: bool = matches!(other.status, CaseStatus::Resolved | CaseStatus::Closed);
+            if link.relation == CaseRelation::Duplicate
+                && duplicate.is_none()
+                && (same_customer || resolved)
+            {
+                duplicate = Some(other);
+            }
+            self.support_case.link_case(link);
+        }
+        duplicate
+    }
+    // Merge into an open duplicate or answer with the resolution of a resolved one.
+    // Returns whether the case is fully handled.
+    fn handle_duplicate(&mut self, mut duplicate: SupportCase) -> bool {
+        if matches!(duplicate.status, CaseStatus::Resolved | CaseStatus::Closed) {
+            // The English resolution, translated for this customer like any other reply
+            let Some(response) = duplicate
+                .original_response
+                .clone()
+                .or(duplicate.support_response.clone())
+            else {
+                return false;
+            };
+            if !self.similarity_config.reuse_resolutions {
+                return false;
+            }
+            self.support_case.duplicate_of = Some(duplicate.case_id);
+            self.support_case.support_response = Some(response);
+            self.request_transition(
+                CaseStatus::AwaitingCustomer,
+                format!("Reused resolution of case {}", duplicate.case_id).as_str(),
+            );
+            return true;
+        }
+
+        // The customer followed up, so a case waiting for them is worked on again
+        duplicate.add_customer_message(self.support_case.customer_query.clone());
+        if duplicate.status == CaseStatus::AwaitingCustomer {
+            let reason: String = format!("Customer followed up in case {}", self.support_case.case_id);
+            if let Err(e) = duplicate.transition(CaseStatus::InProgress, "Coordinator", &reason) {
+                CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+            }
+        }
+        persist_case(self.store.as_mut(), &duplicate);
+
+        self.support_case.duplicate_of = Some(duplicate.case_id);
+        self.support_case.support_response = Some(format!(
+            "Your request was added to case {}, which we are already working on.",
+            duplicate.case_id
+        ));
+        self.request_transition(
+            CaseStatus::Closed,
+            format!("Merged into case {}", duplicate.case_id).as_str(),
+        );
+        true
+    }
+    // Agents reply in English; the customer gets the reply in their language
+    async fn send_reply(&mut self) {
+        if self.translate_responses {
+            let mut translation: AgentTranslation = AgentTranslation::new();
+            if let Err(e) = translation.execute(&mut self.support_case).await {
+                CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+            }
+        }
+        // The translation may say more than the checked English reply
+        guard_reply(&mut self.support_case, &self.policy_config, &mut None);
+        self.remember_reply();
+        if let Some(response) = &self.support_case.support_response {
+            CLIPrint::Default.out("Customer Support", response);
+        }
+        persist_case(self.store.as_mut(), &self.support_case);
+    }
+    // The customer support agent remembers the reply that was sent, in English, not its draft
+    fn remember_reply(&mut self) {
+        let Some(reply) = self
+            .support_case
+            .original_response
+            .clone()
+            .or_else(|| self.support_case.support_response.clone())
+        else {
+            return;
+        };
+        let Some(common) = self
+            .agents
+            .iter()
+            .map(|agent| agent.get_common_from_agent())
+            .find(|common| common.get_role() == "Customer Support")
+        else {
+            return;
+        };
+        let role: String = common.get_role().clone();
+        let message: String = self.support_case.latest_customer_message().to_string();
+        let memory: &mut AgentMemory = self.support_case.memory_mut(&role);
+        memory.remember("user", &message);
+        memory.remember("assistant", &reply);
+        self.support_case.updated();
+    }
     pub async fn handle_support_request(&mut self) {
         self.create_agents();
+        if let Err(e) = self.attach_customer() {
+            CLIPrint::Warning.out("Coordinator", format!("No customer profile: {}", e).as_str());
+        }
+        let profile_language: Option<String> = self
+            .support_case
+            .customer
+            .as_ref()
+            .map(|customer| customer.language.clone());
+        let language: CaseLanguage =
+            detect_language(&self.support_case.customer_query, profile_language.as_deref());
+        CLIPrint::Info.out(
+            "Coordinator",
+            format!(
+                "Customer language: {} ({:.2})",
+                language.name, language.confidence
+            )
+            .as_str(),
+        );
+        self.support_case.language = Some(language);
+        self.request_transition(CaseStatus::InProgress, "Support request received");
+        track_case(&mut self.support_case, &self.priority_config, &self.sla_config);
+        persist_case(self.store.as_mut(), &self.support_case);
+
+        // Duplicates are merged or answered with an earlier resolution without involving agents
+        if let Some(duplicate) = self.link_similar_cases().await {
+            let handled: bool = self.handle_duplicate(duplicate);
+            track_case(&mut self.support_case, &self.priority_config, &self.sla_config);
+            persist_case(self.store.as_mut(), &self.support_case);
+            if handled {
+                self.send_reply().await;
+                return;
+            }
+        }
 
+        // Categories decide the route before any other agent looks at the case
+        let mut categorization: AgentCategorization = AgentCategorization::new(self.taxonomy.clone());
+        if let Err(e) = categorization.execute(&mut self.support_case).await {
+            CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+        }
+        route_case(&mut self.support_case, &self.taxonomy);
+        persist_case(self.store.as_mut(), &self.support_case);
+
+        // Known questions skip the model calls for intent and reply
+        if !answer_from_faq(&mut self.support_case, &self.faq) {
+            self.follow_intent().await;
+        }
+        self.run_agents().await;
+    }
+    // Follow-up message from the customer on a stored case
+    pub async fn handle_customer_reply(&mut self, message: String) {
+        self.create_agents();
+        self.support_case.add_customer_message(message);
+        // The agents work on the English text of the previous reply
+        if let Some(original) = self.support_case.original_response.take() {
+            self.support_case.support_response = Some(original);
+        }
+        match self.support_case.status {
+            CaseStatus::Resolved | CaseStatus::Closed => {
+                self.request_transition(CaseStatus::Reopened, "Customer replied");
+                self.request_transition(CaseStatus::InProgress, "Customer replied");
+            }
+            CaseStatus::AwaitingCustomer | CaseStatus::Reopened | CaseStatus::New => {
+                self.request_transition(CaseStatus::InProgress, "Customer replied")
+            }
+            CaseStatus::InProgress | CaseStatus::Escalated => {}
+        }
+        track_case(
+            &mut self.support_case,
+            &self.priority_config,
+            &self.sla_config,
+        );
+        persist_case(self.store.as_mut(), &self.support_case);
+        if !answer_from_faq(&mut self.support_case, &self.faq) {
+            self.follow_intent().await;
+        }
+        self.run_agents().await;
+    }
+    // Classifies the latest message and answers, escalates or runs an action for it
+    async fn follow_intent(&mut self) {
+        let mut intent_agent: AgentIntent = AgentIntent::new();
+        if let Err(e) = intent_agent.execute(&mut self.support_case).await {
+            CLIPrint::Warning.out("Coordinator", e.to_string().as_str());
+        }
+        let Some(intent) = self.support_case.current_intent().cloned() else {
+            return;
+        };
+        if self.support_case.status == CaseStatus::Escalated {
+            return;
+        }
+        match self.intent_config.route_for(&intent) {
+            IntentRoute::Answer => {}
+            IntentRoute::Escalate => {
+                CLIPrint::Info.out(
+                    "Coordinator",
+                    format!("Routing '{}' case to escalation", intent.kind).as_str(),
+                );
+                self.request_transition(
+                    CaseStatus::Escalated,
+                    &format!(
+                        "Intent '{}' (confidence {:.2})",
+                        intent.kind, intent.confidence
+                    ),
+                );
+            }
+            IntentRoute::Action { action } => {
+                let reason: String = format!("Intent {}", intent);
+                // Actions the policy forbids are left to escalation
+                let violations: Vec<PolicyViolation> = self
+                    .policy_config
+                    .check_action(&mut self.support_case, &action);
+                if let Some(violation) = violations.first() {
+                    self.request_transition(
+                        CaseStatus::Escalated,
+                        &format!("Action '{}' violates policy: {}", action, violation.detail),
+                    );
+                    return;
+                }
+                let result: ActionResult = ActionRegistry::from_config(&ActionConfig::load())
+                    .run(&mut self.support_case, &action, &reason)
+                    .await;
+                CLIPrint::Info.out(
+                    "Coordinator",
+                    format!(
+                        "Action '{}' for {} {}: {}",
+                        result.action, intent.kind, result.status, result.output
+                    )
+                    .as_str(),
+                );
+            }
+        }
+        persist_case(self.store.as_mut(), &self.support_case);
+    }
+    // Runs the agents, translates the reply and leaves the case waiting for the customer
+    async fn run_agents(&mut self) {
+        let mut checked_reply: Option<String> = None;
         for agent in &mut self.agents {
             agent
                 .execute(&mut self.support_case)
                 .await
                 .expect("Should have executed agent");
+            guard_reply(
+                &mut self.support_case,
+                &self.policy_config,
+                &mut checked_reply,
+            );
+            compact_memory(&mut self.support_case, &self.memory_config).await;
+            follow_sentiment(&mut self.support_case, &self.sentiment_config);
+            track_case(&mut self.support_case, &self.priority_config, &self.sla_config);
+            persist_case(self.store.as_mut(), &self.support_case);
+        }
+
+        self.send_reply().await;
+
+        // Answered cases wait for the customer unless an agent moved them elsewhere
+        if self.support_case.status == CaseStatus::InProgress
+            && self.support_case.support_response.is_some()
+        {
+            self.request_transition(CaseStatus::AwaitingCustomer, "Response sent to customer");
+        }
+
+        let evaluation: SlaEvaluation =
+            track_case(&mut self.support_case, &self.priority_config, &self.sla_config);
+        if evaluation.needs_attention() {
+            CLIPrint::Warning.out(
+                "Coordinator",
+                format!(
+                    "SLA for {} case: first response {}, resolution {}",
+                    self.support_case.priority, evaluation.first_response, evaluation.resolution
+                )
+                .as_str(),
+            );
         }
+        persist_case(self.store.as_mut(), &self.support_case);
     }
 }
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
+
+use super::priority::Priority;
+use super::support_case::SupportCase;
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_ACTION_CONFIG_PATH: &str = "config/actions.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionMode {
+    // Record what would be done without side effects
+    DryRun,
+    Execute,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    Planned,
+    Succeeded,
+    Failed,
+}
+
+impl fmt::Display for ActionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Planned => "planned",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Parameters handed to an action handler, extracted from the case
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionRequest {
+    pub action: String,
+    pub reason: String,
+    pub case_id: Uuid,
+    pub support_context: String,
+    pub customer_id: Option<Uuid>,
+    pub customer_name: Option<String>,
+    pub customer_message: String,
+    pub priority: Priority,
+}
+
+impl ActionRequest {
+    pub fn from_case(support_case: &SupportCase, action: &str, reason: &str) -> Self {
+        Self {
+            action: action.to_string(),
+            reason: reason.to_string(),
+            case_id: support_case.case_id,
+            support_context: support_case.support_context.clone(),
+            customer_id: support_case.customer_id,
+            customer_name: support_case
+                .customer
+                .as_ref()
+                .map(|customer| customer.name.clone()),
+            customer_message: support_case.latest_customer_message().to_string(),
+            priority: support_case.priority,
+        }
+    }
+}
+
+// Outcome of one action run, stored on the case
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionResult {
+    pub action: String,
+    pub handler: String,
+    pub mode: ActionMode,
+    pub status: ActionStatus,
+    pub parameters: Value,
+    // What the handler did or would do, or why it failed
+    pub output: String,
+    pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HandlerConfig {
+    // Writes each request as a JSON file into 'directory'
+    File {
+        directory: String,
+    },
+    // Sends each request as JSON to a local endpoint
+    Http {
+        url: String,
+        #[serde(default = "default_method")]
+        method: String,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+// Handler for actions named like 'action'; names are matched like escalation decisions
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionRoute {
+    pub action: String,
+    pub handler: HandlerConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActionConfig {
+    pub mode: ActionMode,
+    pub routes: Vec<ActionRoute>,
+    // Handles actions without a route; without one they are only recorded
+    pub default_handler: Option<HandlerConfig>,
+}
+
+impl Default for ActionConfig {
+    fn default() -> Self {
+        Self {
+            mode: ActionMode::DryRun,
+            routes: Vec::from([]),
+            default_handler: Some(HandlerConfig::File {
+                directory: "actions".to_string(),
+            }),
+        }
+    }
+}
+
+impl ActionConfig {
+    // Reads ACTION_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("ACTION_CONFIG_PATH", DEFAULT_ACTION_CONFIG_PATH)
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_ATTACHMENT_CONFIG_PATH: &str = "config/attachments.json";
+
+// MIME types recognized from the file extension
+const MIME_TYPES: [(&str, &str); 11] = [
+    ("txt", "text/plain"),
+    ("log", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("json", "application/json"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AttachmentConfig {
+    // Directory attachments are copied to, one sub directory per case
+    pub storage_dir: String,
+    pub max_size_bytes: u64,
+    pub allowed_mime_types: Vec<String>,
+    // Extracted text is cut to this many characters before it reaches agent prompts
+    pub max_extracted_chars: usize,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            storage_dir: "attachments".to_string(),
+            max_size_bytes: 10 * 1024 * 1024,
+            allowed_mime_types: MIME_TYPES
+                .iter()
+                .map(|(_, mime_type)| mime_type.to_string())
+                .collect(),
+            max_extracted_chars: 4000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Attachment {
+    pub attachment_id: Uuid,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    // SHA-256 of the file content
+    pub checksum: String,
+    pub storage_path: String,
+    // Text of plain-text and PDF attachments, as shown to agents
+    pub extracted_text: Option<String>,
+    pub added_at: DateTime<Local>,
+}
+
+impl Attachment {
+    pub fn prompt_text(&self) -> String {
+        match &self.extracted_text {
+            Some(text) => format!("[{} ({})]: {}", self.file_name, self.mime_type, text),
+            None => format!("[{} ({}), content not readable]", self.file_name, self.mime_type),
+        }
+    }
+}
+
+pub fn mime_type_for(path: &Path) -> &'static str {
+    let extension: String = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, mime_type)| *mime_type)
+        .unwrap_or("application/octet-stream")
+}
+
+// Whether the content looks like the type its extension names: binary formats by their magic
+// bytes, text formats by being UTF-8 without NUL bytes
+pub fn content_matches(mime_type: &str, content: &[u8]) -> bool {
+    match mime_type {
+        "application/pdf" => content.starts_with(b"%PDF-"),
+        "image/png" => content.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => content.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "image/gif" => content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a"),
+        "image/webp" => content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP",
+        _ if mime_type.starts_with("text/") || mime_type == "application/json" => {
+            !content.contains(&0) && std::str::from_utf8(content).is_ok()
+        }
+        _ => true,
+    }
+}
+
+// Text content of plain-text and PDF files, cut to 'max_chars'
+pub fn extract_text(mime_type: &str, content: &[u8], max_chars: usize) -> Option<String> {
+    let text: String = if mime_type.starts_with("text/") || mime_type == "application/json" {
+        String::from_utf8_lossy(content).to_string()
+    } else if mime_type == "application/pdf" {
+        // The PDF parser panics on some malformed files
+        std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(content))
+            .ok()?
+            .ok()?
+    } else {
+        return None;
+    };
+    let text: String = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(text.chars().take(max_chars).collect())
+}
+
+impl AttachmentConfig {
+    // Reads ATTACHMENT_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("ATTACHMENT_CONFIG_PATH", DEFAULT_ATTACHMENT_CONFIG_PATH)
+    }
+
+    // Rejects files that are too large or of a type that is not allowed
+    pub fn check(&self, file_name: &str, mime_type: &str, size_bytes: u64) -> Result<(), Box<dyn Error>> {
+        if size_bytes > self.max_size_bytes {
+            return Err(format!(
+                "Attachment '{}' is {} bytes, the limit is {} bytes",
+                file_name, size_bytes, self.max_size_bytes
+            )
+            .into());
+        }
+        if !self.allowed_mime_types.iter().any(|allowed| allowed == mime_type) {
+            return Err(format!(
+                "Attachment '{}' has type '{}', which is not allowed",
+                file_name, mime_type
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use uuid::Uuid;
+
+use super::support_case::SupportCase;
+
+// Previous hash of the first entry in the log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Case fields that get their own audit entries instead of showing up as changed fields
+const ITEMIZED_FIELDS: [&str; 2] = ["status_history", "trace"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    CaseCreated,
+    CaseUpdated,
+    StatusChanged,
+    AgentDecision,
+    CaseMigrated,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::CaseCreated => "case created",
+            Self::CaseUpdated => "case updated",
+            Self::StatusChanged => "status changed",
+            Self::AgentDecision => "agent decision",
+            Self::CaseMigrated => "case migrated",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Something that happened to a case, before it is chained into the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    pub actor: String,
+    pub details: Value,
+}
+
+// Entry of the append-only audit log. Each entry commits to the previous one through
+// 'previous_hash' and to the case JSON exactly as stored after the change through
+// 'case_snapshot' and its hash 'case_hash'.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub case_id: Uuid,
+    pub at: DateTime<Local>,
+    pub action: AuditAction,
+    pub actor: String,
+    pub details: Value,
+    pub case_snapshot: String,
+    pub case_hash: String,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl AuditEntry {
+    // 'case_snapshot' is the case JSON as the store wrote it
+    pub fn new(
+        previous: Option<&AuditEntry>,
+        case_id: Uuid,
+        case_snapshot: &str,
+        event: AuditEvent,
+    ) -> Self {
+        let mut entry: AuditEntry = Self {
+            sequence: previous.map(|entry| entry.sequence + 1).unwrap_or(1),
+            case_id,
+            at: Local::now(),
+            action: event.action,
+            actor: event.actor,
+            details: event.details,
+            case_snapshot: case_snapshot.to_string(),
+            case_hash: sha256_hex(case_snapshot.as_bytes()),
+            previous_hash: previous
+                .map(|entry| entry.hash.clone())
+                .unwrap_or(GENESIS_HASH.to_string()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+    // Hash over every field except 'hash' itself; the snapshot is covered by 'case_hash'
+    pub fn compute_hash(&self) -> String {
+        let content: Value = json!({
+            "sequence": self.sequence,
+            "case_id": self.case_id,
+            "at": self.at,
+            "action": self.action,
+            "actor": self.actor,
+            "details": self.details,
+            "case_hash": self.case_hash,
+            "previous_hash": self.previous_hash,
+        });
+        sha256_hex(content.to_string().as_bytes())
+    }
+}
+
+// Entries for one write of a case, chained after 'last'
+pub fn chain_entries(
+    last: Option<AuditEntry>,
+    case_id: Uuid,
+    case_snapshot: &str,
+    events: Vec<AuditEvent>,
+) -> Vec<AuditEntry> {
+    let mut entries: Vec<AuditEntry> = Vec::from([]);
+    for event in events {
+        let entry: AuditEntry =
+            AuditEntry::new(entries.last().or(last.as_ref()), case_id, case_snapshot, event);
+        entries.push(entry);
+    }
+    entries
+}
+
+fn top_level_fields(support_case: &SupportCase) -> Map<String, Value> {
+    match serde_json::to_value(support_case) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+// Events that turn 'previous' into 'current': new status transitions and model calls are
+// itemized, any other change is recorded as one update listing the changed fields
+pub fn case_events(
+    previous: Option<&SupportCase>,
+    current: &SupportCase,
+    actor: &str,
+) -> Vec<AuditEvent> {
+    let mut events: Vec<AuditEvent> = Vec::from([]);
+    let (known_transitions, known_calls) = match previous {
+        Some(previous) => (previous.status_history.len(), previous.trace.len()),
+        None => {
+            events.push(AuditEvent {
+                action: AuditAction::CaseCreated,
+                actor: actor.to_string(),
+                details: json!({
+                    "support_context": current.support_context,
+                    "customer_query": current.customer_query,
+                }),
+            });
+            (0, 0)
+        }
+    };
+
+    if let Some(previous) = previous {
+        let before: Map<String, Value> = top_level_fields(previous);
+        let after: Map<String, Value> = top_level_fields(current);
+        let changed: Vec<Value> = after
+            .iter()
+            .filter(|(field, value)| {
+                !ITEMIZED_FIELDS.contains(&field.as_str()) && before.get(*field) != Some(*value)
+            })
+            .map(|(field, value)| json!({ "field": field, "value": value }))
+            .collect();
+        if !changed.is_empty() {
+            events.push(AuditEvent {
+                action: AuditAction::CaseUpdated,
+                actor: actor.to_string(),
+                details: json!({ "changes": changed }),
+            });
+        }
+    }
+
+    for transition in current.status_history.iter().skip(known_transitions) {
+        events.push(AuditEvent {
+            action: AuditAction::StatusChanged,
+            actor: transition.requested_by.clone(),
+            details: json!({
+                "from": transition.from,
+                "to": transition.to,
+                "reason": transition.reason,
+            }),
+        });
+    }
+    for call in current.trace.iter().skip(known_calls) {
+        events.push(AuditEvent {
+            action: AuditAction::AgentDecision,
+            actor: call.agent.clone(),
+            details: json!({
+                "response": call.response,
+                "parsed": call.parsed,
+                "model": call.model,
+                "truncation": call.truncation,
+                "errors": call.errors,
+            }),
+        });
+    }
+    events
+}
+
+// Problems found in the log, in log order, followed by problems with stored cases.
+// 'cases' holds every stored case as (case_id, JSON as stored).
+pub fn verify_log(entries: &[AuditEntry], cases: &[(Uuid, String)]) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::from([]);
+    let mut previous: Option<&AuditEntry> = None;
+    for entry in entries {
+        let expected_sequence: u64 = previous.map(|entry| entry.sequence + 1).unwrap_or(1);
+        if entry.sequence != expected_sequence {
+            problems.push(format!(
+                "Entry {}: expected sequence {}, entries are missing or reordered",
+                entry.sequence, expected_sequence
+            ));
+        }
+        let expected_previous: &str = previous
+            .map(|entry| entry.hash.as_str())
+            .unwrap_or(GENESIS_HASH);
+        if entry.previous_hash != expected_previous {
+            problems.push(format!(
+                "Entry {}: does not link to the previous entry",
+                entry.sequence
+            ));
+        }
+        if entry.hash != entry.compute_hash() {
+            problems.push(format!(
+                "Entry {}: content does not match its hash",
+                entry.sequence
+            ));
+        }
+        if entry.case_hash != sha256_hex(entry.case_snapshot.as_bytes()) {
+            problems.push(format!(
+                "Entry {}: case snapshot does not match its hash",
+                entry.sequence
+            ));
+        }
+        previous = Some(entry);
+    }
+
+    for (case_id, stored) in cases {
+        match entries.iter().rev().find(|entry| entry.case_id == *case_id) {
+            Some(entry) if entry.case_hash != sha256_hex(stored.as_bytes()) => {
+                problems.push(format!(
+                    "Case {}: stored case differs from the last audited version (entry {})",
+                    case_id, entry.sequence
+                ))
+            }
+            Some(_) => {}
+            None => problems.push(format!("Case {}: no audit entries", case_id)),
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::case_status::CaseStatus;
+
+    fn chain(entries: &mut Vec<AuditEntry>, support_case: &SupportCase, events: Vec<AuditEvent>) {
+        let snapshot: String = serde_json::to_string(support_case).unwrap();
+        let chained: Vec<AuditEntry> =
+            chain_entries(entries.last().cloned(), support_case.case_id, &snapshot, events);
+        entries.extend(chained);
+    }
+
+    fn stored(support_case: &SupportCase) -> (Uuid, String) {
+        (support_case.case_id, serde_json::to_string(support_case).unwrap())
+    }
+
+    #[test]
+    fn tests_audit_log_detects_tampering() {
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        let mut entries: Vec<AuditEntry> = Vec::from([]);
+        chain(&mut entries, &support_case, case_events(None, &support_case, "Coordinator"));
+
+        let previous: SupportCase = support_case.clone();
+        support_case
+            .transition(CaseStatus::Escalated, "Psychologist", "Negative sentiment")
+            .unwrap();
+        support_case.support_response = Some("Offer a free night".to_string());
+        let events: Vec<AuditEvent> = case_events(Some(&previous), &support_case, "Coordinator");
+        assert_eq!(
+            events.iter().map(|event| event.action).collect::<Vec<AuditAction>>(),
+            [AuditAction::CaseUpdated, AuditAction::StatusChanged]
+        );
+        assert_eq!(events[1].actor, "Psychologist");
+        chain(&mut entries, &support_case, events);
+        assert_eq!(entries[2].previous_hash, entries[1].hash);
+        assert!(verify_log(&entries, &[stored(&support_case)]).is_empty());
+
+        let mut altered_case: SupportCase = support_case.clone();
+        altered_case.support_response = Some("Offer a full refund".to_string());
+        assert_eq!(verify_log(&entries, &[stored(&altered_case)]).len(), 1);
+
+        // The stored bytes are compared, so even a reformatted case is reported
+        let (case_id, data) = stored(&support_case);
+        let reformatted: String = serde_json::to_string_pretty(
+            &serde_json::from_str::<Value>(&data).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(verify_log(&entries, &[(case_id, reformatted)]).len(), 1);
+
+        let mut altered_log: Vec<AuditEntry> = entries.clone();
+        altered_log[1].actor = "Operator".to_string();
+        assert!(!verify_log(&altered_log, &[stored(&support_case)]).is_empty());
+
+        let mut altered_snapshot: Vec<AuditEntry> = entries.clone();
+        altered_snapshot[2].case_snapshot = serde_json::to_string(&altered_case).unwrap();
+        assert_eq!(verify_log(&altered_snapshot, &[stored(&support_case)]).len(), 1);
+
+        let mut shortened_log: Vec<AuditEntry> = entries.clone();
+        shortened_log.remove(1);
+        assert!(!verify_log(&shortened_log, &[stored(&support_case)]).is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::error::Error;
+
+use super::{
+    case_status::CaseStatus,
+    escalation::{EscalationChoice, EscalationDecision},
+    support_case::SupportCase,
+};
+
+// Bump whenever SupportCase changes in a way older documents cannot be read with;
+// new fields with serde defaults do not need a bump
+pub const CASE_SCHEMA_VERSION: u32 = 4;
+
+// Versioned envelope used to move cases between tools
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaseExport {
+    pub schema_version: u32,
+    pub exported_at: DateTime<Local>,
+    pub case: SupportCase,
+}
+
+impl CaseExport {
+    pub fn new(case: SupportCase) -> Self {
+        Self {
+            schema_version: CASE_SCHEMA_VERSION,
+            exported_at: Local::now(),
+            case,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let mut value: Value = serde_json::from_str(json)?;
+        let schema_version: u64 = value["schema_version"]
+            .as_u64()
+            .ok_or("Missing case schema version")?;
+        if schema_version > CASE_SCHEMA_VERSION as u64 {
+            return Err(format!(
+                "Unsupported case schema version {} (supported up to {})",
+                schema_version, CASE_SCHEMA_VERSION
+            )
+            .into());
+        }
+        if schema_version < 2 {
+            upgrade_v1_case(&mut value["case"]);
+        }
+        let mut export: CaseExport = serde_json::from_value(value)?;
+        export.schema_version = CASE_SCHEMA_VERSION;
+        Ok(export)
+    }
+}
+
+// v1 tracked the lifecycle through 'should_escalate', 'escalated' and
+// 'needs_upper_management_attention' flags; SQLite stores migrate their cases with it too
+pub fn upgrade_v1_case(case: &mut Value) {
+    let escalated: bool = case["escalated"].as_bool().unwrap_or(false);
+    let status: CaseStatus = if escalated {
+        CaseStatus::Escalated
+    } else if !case["support_response"].is_null() {
+        CaseStatus::AwaitingCustomer
+    } else {
+        CaseStatus::New
+    };
+    case["status"] = json!(status);
+    case["status_history"] = json!([]);
+    if case["needs_upper_management_attention"].as_bool().unwrap_or(false) {
+        let mut decision: Value = json!(EscalationDecision::new(
+            EscalationChoice::UpperManagement,
+            "Flagged for upper management",
+            "",
+            1.0,
+        ));
+        decision["decided_at"] = case["updated_at"].clone();
+        case["escalation_decision"] = decision;
+    }
+    if let Some(fields) = case.as_object_mut() {
+        fields.remove("should_escalate");
+        fields.remove("escalated");
+        fields.remove("needs_upper_management_attention");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        ai::chatgpt::Message,
+        general::{llm_call::LlmCall, sentiment::Polarity},
+    };
+
+    #[test]
+    fn tests_case_export_round_trip() {
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case.support_response = Some("We will send someone right away.".to_string());
+        let mut call: LlmCall = LlmCall::new(
+            "Customer Support",
+            "You are a receptionist or assistant.",
+            Vec::from([Message {
+                role: "system".to_string(),
+                content: "The air conditioning in my room is broken.".to_string(),
+            }]),
+        );
+        call.response = Some("We will send someone right away.".to_string());
+        support_case.trace.push(call);
+
+        let json: String = CaseExport::new(support_case.clone()).to_json().unwrap();
+        let imported: CaseExport = CaseExport::from_json(&json).unwrap();
+
+        assert_eq!(imported.schema_version, CASE_SCHEMA_VERSION);
+        assert_eq!(imported.case.case_id, support_case.case_id);
+        assert_eq!(imported.case.created_at, support_case.created_at);
+        assert_eq!(imported.case.trace.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&imported.case).unwrap(),
+            serde_json::to_value(&support_case).unwrap()
+        );
+    }
+
+    #[test]
+    fn tests_case_export_rejects_newer_schema() {
+        let support_case: SupportCase =
+            SupportCase::new("5 Star Hotel".to_string(), "Hello".to_string());
+        let mut value: Value = serde_json::to_value(CaseExport::new(support_case)).unwrap();
+        value["schema_version"] = json!(CASE_SCHEMA_VERSION + 1);
+
+        assert!(CaseExport::from_json(&value.to_string()).is_err());
+    }
+
+    #[test]
+    fn tests_case_export_upgrades_v1() {
+        let v1: Value = json!({
+            "schema_version": 1,
+            "exported_at": "2024-07-20T10:15:00+02:00",
+            "case": {
+                "case_id": "6f1c2a9e-1111-4222-8333-444455556666",
+                "support_context": "5 Star Hotel",
+                "customer_query": "My shower is cold",
+                "support_response": null,
+                "sentiment": "Negative",
+                "should_escalate": true,
+                "escalated": true,
+                "needs_upper_management_attention": true,
+                "created_at": "2024-07-20T10:00:00+02:00",
+                "updated_at": "2024-07-20T10:00:00+02:00",
+                "trace": [],
+                "supported_actions": []
+            }
+        });
+
+        let imported: CaseExport = CaseExport::from_json(&v1.to_string()).unwrap();
+        assert_eq!(imported.schema_version, CASE_SCHEMA_VERSION);
+        assert_eq!(imported.case.status, CaseStatus::Escalated);
+        assert!(imported.case.is_escalated());
+        assert!(imported.case.needs_upper_management());
+        assert_eq!(
+            imported.case.escalation_decision.unwrap().decided_at,
+            DateTime::parse_from_rfc3339("2024-07-20T10:00:00+02:00").unwrap()
+        );
+        assert_eq!(
+            imported.case.sentiment.map(|sentiment| sentiment.polarity),
+            Some(Polarity::Negative)
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    #[default]
+    New,
+    InProgress,
+    AwaitingCustomer,
+    Escalated,
+    Resolved,
+    Closed,
+    Reopened,
+}
+
+impl CaseStatus {
+    pub const ALL: [CaseStatus; 7] = [
+        CaseStatus::New,
+        CaseStatus::InProgress,
+        CaseStatus::AwaitingCustomer,
+        CaseStatus::Escalated,
+        CaseStatus::Resolved,
+        CaseStatus::Closed,
+        CaseStatus::Reopened,
+    ];
+
+    // Transition table: every status a case may move to from this one
+    pub fn allowed_transitions(&self) -> &'static [CaseStatus] {
+        match self {
+            Self::New => &[Self::InProgress, Self::Escalated, Self::Closed],
+            Self::InProgress => &[
+                Self::AwaitingCustomer,
+                Self::Escalated,
+                Self::Resolved,
+                Self::Closed,
+            ],
+            Self::AwaitingCustomer => &[
+                Self::InProgress,
+                Self::Escalated,
+                Self::Resolved,
+                Self::Closed,
+            ],
+            Self::Escalated => &[Self::InProgress, Self::Resolved, Self::Closed],
+            Self::Resolved => &[Self::Closed, Self::Reopened],
+            Self::Closed => &[Self::Reopened],
+            Self::Reopened => &[Self::InProgress, Self::Escalated, Self::Closed],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: CaseStatus) -> bool {
+        self.allowed_transitions().contains(&next)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::InProgress => "in_progress",
+            Self::AwaitingCustomer => "awaiting_customer",
+            Self::Escalated => "escalated",
+            Self::Resolved => "resolved",
+            Self::Closed => "closed",
+            Self::Reopened => "reopened",
+        }
+    }
+}
+
+impl fmt::Display for CaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for CaseStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|status| status.as_str() == value)
+            .copied()
+            .ok_or_else(|| format!("Unknown case status '{}'", value))
+    }
+}
+
+// Single entry of a case's status history
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusTransition {
+    pub from: CaseStatus,
+    pub to: CaseStatus,
+    pub at: DateTime<Local>,
+    pub requested_by: String,
+    pub reason: String,
+}
@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton, CoreBPE};
+
+use super::memory::truncate;
+use super::support_case::PromptContext;
+use crate::helpers::config::load_config;
+use crate::models::ai::chatgpt::Message;
+
+pub const DEFAULT_CONTEXT_BUDGET_PATH: &str = "config/context_budget.json";
+
+// Tokens every message and every request add on top of their text, as counted by OpenAI
+const TOKENS_PER_MESSAGE: usize = 3;
+const TOKENS_PER_REQUEST: usize = 3;
+
+// What happens to messages older than the latest turns when a request is over budget
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimPolicy {
+    // Older messages are replaced by one message with the start of each
+    Summarize,
+    // Older messages are left out, oldest first
+    Drop,
+}
+
+impl fmt::Display for TrimPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Summarize => write!(f, "summarize"),
+            Self::Drop => write!(f, "drop"),
+        }
+    }
+}
+
+// How a request was shortened to fit the budget, as stored with the call in the case trace
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContextTruncation {
+    pub policy: TrimPolicy,
+    pub max_prompt_tokens: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    // Older messages folded into the summary message
+    pub summarized: usize,
+    // Messages left out of the request
+    pub dropped: usize,
+    // Earlier customer messages, attachments and actions in the prompt context cut short
+    #[serde(default)]
+    pub context_summarized: usize,
+    // Earlier customer messages, attachments and actions left out of the prompt context
+    #[serde(default)]
+    pub context_dropped: usize,
+}
+
+impl fmt::Display for ContextTruncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} of {} tokens ({}: {} summarized, {} dropped, context {} summarized, {} dropped)",
+            self.tokens_before,
+            self.tokens_after,
+            self.max_prompt_tokens,
+            self.policy,
+            self.summarized,
+            self.dropped,
+            self.context_summarized,
+            self.context_dropped
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContextBudgetConfig {
+    // Model whose tokenizer measures requests
+    pub model: String,
+    // Prompt tokens a request may use, leaving the rest of the context window to the reply
+    pub max_prompt_tokens: usize,
+    // Messages right before the system prompt that are never shortened
+    pub keep_recent: usize,
+    pub policy: TrimPolicy,
+    // Characters of each older message kept in the summary message
+    pub summary_entry_chars: usize,
+}
+
+impl Default for ContextBudgetConfig {
+    fn default() -> Self {
+        Self {
+            model: "gpt-4o".to_string(),
+            max_prompt_tokens: 120000,
+            keep_recent: 4,
+            policy: TrimPolicy::Summarize,
+            summary_entry_chars: 200,
+        }
+    }
+}
+
+impl ContextBudgetConfig {
+    // Reads CONTEXT_BUDGET_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("CONTEXT_BUDGET_PATH", DEFAULT_CONTEXT_BUDGET_PATH)
+    }
+}
+
+// One message standing in for the older messages of a request
+fn summary_message(messages: &[Message], entry_chars: usize) -> Message {
+    let entries: Vec<String> = messages
+        .iter()
+        .map(|message| {
+            let speaker: &str = match message.role.as_str() {
+                "user" => "Customer",
+                "assistant" => "You",
+                _ => "Note",
+            };
+            format!("{}: {}", speaker, truncate(&message.content, entry_chars))
+        })
+        .collect();
+    Message {
+        role: "system".to_string(),
+        content: format!("EARLIER CONVERSATION, SHORTENED: {}", entries.join(" | ")),
+    }
+}
+
+pub struct ContextBudget {
+    config: ContextBudgetConfig,
+}
+
+impl ContextBudget {
+    pub fn new(config: ContextBudgetConfig) -> Self {
+        Self { config }
+    }
+
+    // Prompt tokens of a request with the tokenizer of the configured model
+    pub fn count_tokens(&self, messages: &[Message]) -> usize {
+        let count = |bpe: &CoreBPE| -> usize {
+            messages
+                .iter()
+                .map(|message| {
+                    TOKENS_PER_MESSAGE
+                        + bpe.encode_with_special_tokens(&message.role).len()
+                        + bpe.encode_with_special_tokens(&message.content).len()
+                })
+                .sum::<usize>()
+                + TOKENS_PER_REQUEST
+        };
+        // Models the tokenizer does not know are measured like GPT-4
+        match get_tokenizer(&self.config.model) {
+            Some(Tokenizer::O200kBase) => count(&o200k_base_singleton().lock()),
+            _ => count(&cl100k_base_singleton().lock()),
+        }
+    }
+
+    fn over_budget(&self, tokens: usize) -> String {
+        format!(
+            "Request needs {} tokens, over the budget of {}",
+            tokens, self.config.max_prompt_tokens
+        )
+    }
+
+    // Shortens a request to the budget; the last message is the system prompt and is always kept.
+    // Fails when the system prompt alone is over budget
+    pub fn fit(
+        &self,
+        messages: Vec<Message>,
+    ) -> Result<(Vec<Message>, Option<ContextTruncation>), String> {
+        let tokens_before: usize = self.count_tokens(&messages);
+        if tokens_before <= self.config.max_prompt_tokens {
+            return Ok((messages, None));
+        }
+        let mut earlier: Vec<Message> = messages;
+        let Some(prompt) = earlier.pop() else {
+            return Ok((earlier, None));
+        };
+        let recent: Vec<Message> =
+            earlier.split_off(earlier.len().saturating_sub(self.config.keep_recent));
+
+        // Older messages go first, per policy; the latest turns only when that is not enough
+        let mut summarized: usize = 0;
+        let mut kept: Vec<Message> = match self.config.policy {
+            TrimPolicy::Summarize if !earlier.is_empty() => {
+                summarized = earlier.len();
+                Vec::from([summary_message(&earlier, self.config.summary_entry_chars)])
+            }
+            _ => earlier,
+        };
+        kept.extend(recent);
+        kept.push(prompt);
+
+        let mut dropped: usize = 0;
+        let mut tokens_after: usize = self.count_tokens(&kept);
+        while tokens_after > self.config.max_prompt_tokens && kept.len() > 1 {
+            kept.remove(0);
+            if summarized > 0 {
+                dropped += summarized;
+                summarized = 0;
+            } else {
+                dropped += 1;
+            }
+            tokens_after = self.count_tokens(&kept);
+        }
+        if tokens_after > self.config.max_prompt_tokens {
+            return Err(self.over_budget(tokens_after));
+        }
+
+        let truncation: ContextTruncation = ContextTruncation {
+            policy: self.config.policy,
+            max_prompt_tokens: self.config.max_prompt_tokens,
+            tokens_before,
+            tokens_after,
+            summarized,
+            dropped,
+            context_summarized: 0,
+            context_dropped: 0,
+        };
+        Ok((kept, Some(truncation)))
+    }
+
+    // Shortens the prompt context until the system prompt alone fits: entries of the earlier
+    // customer messages, attachments and actions are cut short (per policy), then left out, oldest first
+    fn fit_context(
+        &self,
+        context: &mut PromptContext,
+        prompt: &impl Fn(&PromptContext) -> Message,
+    ) -> Result<(usize, usize), String> {
+        let tokens = |context: &PromptContext| self.count_tokens(&[prompt(context)]);
+        if tokens(context) <= self.config.max_prompt_tokens {
+            return Ok((0, 0));
+        }
+        let sections: usize = context.shortenable_mut().len();
+        let mut summarized: usize = 0;
+        if self.config.policy == TrimPolicy::Summarize {
+            for section in 0..sections {
+                for index in 0..context.shortenable_mut()[section].len() {
+                    let entry: &mut String = &mut context.shortenable_mut()[section][index];
+                    let shortened: String = truncate(entry, self.config.summary_entry_chars);
+                    if shortened == *entry {
+                        continue;
+                    }
+                    *entry = shortened;
+                    summarized += 1;
+                    if tokens(context) <= self.config.max_prompt_tokens {
+                        return Ok((summarized, 0));
+                    }
+                }
+            }
+        }
+        let mut dropped: usize = 0;
+        for section in 0..sections {
+            while !context.shortenable_mut()[section].is_empty() {
+                context.shortenable_mut()[section].remove(0);
+                dropped += 1;
+                if tokens(context) <= self.config.max_prompt_tokens {
+                    return Ok((summarized, dropped));
+                }
+            }
+        }
+        Err(self.over_budget(tokens(context)))
+    }
+
+    // Shortens a request with a case's prompt context: the context first, so the system prompt
+    // fits on its own, then the earlier messages. Fails when the request still does not fit
+    pub fn fit_case_request(
+        &self,
+        earlier: Vec<Message>,
+        context: &mut PromptContext,
+        prompt: impl Fn(&PromptContext) -> Message,
+    ) -> Result<(Vec<Message>, Option<ContextTruncation>), String> {
+        let mut messages: Vec<Message> = earlier.clone();
+        messages.push(prompt(context));
+        let tokens_before: usize = self.count_tokens(&messages);
+
+        let (context_summarized, context_dropped) = self.fit_context(context, &prompt)?;
+        let mut messages: Vec<Message> = earlier;
+        messages.push(prompt(context));
+        let (messages, truncation) = self.fit(messages)?;
+        if context_summarized + context_dropped == 0 {
+            return Ok((messages, truncation));
+        }
+        let truncation: ContextTruncation = ContextTruncation {
+            tokens_before,
+            context_summarized,
+            context_dropped,
+            ..truncation.unwrap_or(ContextTruncation {
+                policy: self.config.policy,
+                max_prompt_tokens: self.config.max_prompt_tokens,
+                tokens_before,
+                tokens_after: self.count_tokens(&messages),
+                summarized: 0,
+                dropped: 0,
+                context_summarized: 0,
+                context_dropped: 0,
+            })
+        };
+        Ok((messages, Some(truncation)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::request::prepare_message;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn tests_context_budget() {
+        let long: String = "The air conditioning in my room is still broken. ".repeat(20);
+        let mut messages: Vec<Message> = Vec::from([message(
+            "system",
+            "WHAT YOU REMEMBER FROM EARLIER: Discount offered",
+        )]);
+        for _ in 0..3 {
+            messages.push(message("user", &long));
+            messages.push(message("assistant", "We sent a mechanic."));
+        }
+        messages.push(message(
+            "system",
+            "YOUR INSTRUCTIONS: Answer the guest QUERY: Still broken!",
+        ));
+
+        let mut config: ContextBudgetConfig = ContextBudgetConfig {
+            model: "gpt-4o".to_string(),
+            max_prompt_tokens: 100000,
+            keep_recent: 2,
+            policy: TrimPolicy::Summarize,
+            summary_entry_chars: 20,
+        };
+        let budget: ContextBudget = ContextBudget::new(config.clone());
+        let tokens: usize = budget.count_tokens(&messages);
+        assert!(tokens > 600);
+        let (fitted, truncation) = budget.fit(messages.clone()).unwrap();
+        assert_eq!(fitted.len(), messages.len());
+        assert!(truncation.is_none());
+
+        // The summary replaces the older messages; the latest turns and the prompt stay as they are
+        config.max_prompt_tokens = 450;
+        let (fitted, truncation) = ContextBudget::new(config.clone())
+            .fit(messages.clone())
+            .unwrap();
+        let truncation: ContextTruncation = truncation.unwrap();
+        assert_eq!(fitted.len(), 4);
+        assert!(fitted[0]
+            .content
+            .starts_with("EARLIER CONVERSATION, SHORTENED: Note: WHAT YOU REMEMBER"));
+        assert!(fitted[0]
+            .content
+            .contains("Customer: The air conditioning..."));
+        assert_eq!(fitted[3].content, messages[7].content);
+        assert_eq!((truncation.summarized, truncation.dropped), (5, 0));
+        assert_eq!(truncation.tokens_before, tokens);
+        assert!(truncation.tokens_after <= 450);
+
+        // Dropping leaves out the oldest messages until the request fits
+        config.policy = TrimPolicy::Drop;
+        let (fitted, truncation) = ContextBudget::new(config.clone())
+            .fit(messages.clone())
+            .unwrap();
+        let truncation: ContextTruncation = truncation.unwrap();
+        assert_eq!((truncation.summarized, truncation.dropped), (0, 3));
+        assert_eq!(fitted[0].content, messages[3].content);
+
+        // A prompt over budget on its own is not sent
+        config.max_prompt_tokens = 10;
+        assert!(ContextBudget::new(config).fit(messages).is_err());
+    }
+
+    #[test]
+    fn tests_context_budget_case_request() {
+        let long: String = "The air conditioning in my room is still broken. ".repeat(20);
+        let context: PromptContext = PromptContext {
+            header: "Hotel support".to_string(),
+            messages: Vec::from([long.clone(), long.clone()]),
+            attachments: Vec::from([format!("[photo.txt (text/plain)]: {}", long)]),
+            actions: Vec::from(["send_mechanic (completed)".to_string()]),
+            notes: Vec::from(["CUSTOMER MESSAGE: Still broken!".to_string()]),
+            ..Default::default()
+        };
+        let prompt = |context: &PromptContext| {
+            prepare_message("Answer the guest", &context.text(), "Still broken!")
+        };
+        let memory: Vec<Message> = Vec::from([
+            message("user", &long),
+            message("assistant", "We sent a mechanic."),
+        ]);
+        let mut config: ContextBudgetConfig = ContextBudgetConfig {
+            model: "gpt-4o".to_string(),
+            max_prompt_tokens: 100000,
+            keep_recent: 0,
+            policy: TrimPolicy::Summarize,
+            summary_entry_chars: 20,
+        };
+
+        let mut fitted_context: PromptContext = context.clone();
+        let (fitted, truncation) = ContextBudget::new(config.clone())
+            .fit_case_request(memory.clone(), &mut fitted_context, prompt)
+            .unwrap();
+        assert_eq!(fitted.len(), 3);
+        assert!(truncation.is_none());
+        assert_eq!(fitted_context, context);
+
+        // The oldest customer messages and attachments are cut short before the prompt is built
+        config.max_prompt_tokens = 250;
+        let (fitted, truncation) = ContextBudget::new(config.clone())
+            .fit_case_request(memory.clone(), &mut fitted_context, prompt)
+            .unwrap();
+        let truncation: ContextTruncation = truncation.unwrap();
+        assert_eq!(fitted_context.messages[0], "The air conditioning...");
+        assert!(fitted
+            .last()
+            .unwrap()
+            .content
+            .contains("CUSTOMER MESSAGE: Still broken!"));
+        assert!(truncation.context_summarized > 0 && truncation.context_dropped == 0);
+        assert!(truncation.tokens_after <= 250 && truncation.tokens_before > 250);
+
+        // Dropping leaves them out, oldest first; the header and notes always stay
+        config.policy = TrimPolicy::Drop;
+        let mut fitted_context: PromptContext = context.clone();
+        let (_, truncation) = ContextBudget::new(config.clone())
+            .fit_case_request(memory.clone(), &mut fitted_context, prompt)
+            .unwrap();
+        assert!(fitted_context.messages.is_empty() && fitted_context.attachments.is_empty());
+        assert_eq!(fitted_context.actions, context.actions);
+        assert_eq!(truncation.unwrap().context_dropped, 3);
+
+        // Fails when even the shortest context is over budget
+        config.max_prompt_tokens = 20;
+        let mut fitted_context: PromptContext = context;
+        assert!(ContextBudget::new(config)
+            .fit_case_request(memory, &mut fitted_context, prompt)
+            .is_err());
+    }
+}
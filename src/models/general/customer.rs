@@ -0,0 +1,159 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::case_status::CaseStatus;
+use super::support_case::SupportCase;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomerTier {
+    #[default]
+    Standard,
+    Premium,
+    Vip,
+}
+
+impl CustomerTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Premium => "premium",
+            Self::Vip => "vip",
+        }
+    }
+}
+
+impl fmt::Display for CustomerTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for CustomerTier {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "standard" => Ok(Self::Standard),
+            "premium" => Ok(Self::Premium),
+            "vip" => Ok(Self::Vip),
+            _ => Err(format!("Unknown customer tier '{}'", value)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactKind {
+    Email,
+    Phone,
+    Chat,
+    Social,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContactChannel {
+    pub kind: ContactKind,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Customer {
+    pub customer_id: Uuid,
+    pub name: String,
+    pub contact_channels: Vec<ContactChannel>,
+    pub tier: CustomerTier,
+    pub lifetime_value: f64,
+    pub language: String,
+    pub created_at: DateTime<Local>,
+}
+
+impl Customer {
+    pub fn new(name: String) -> Self {
+        Self {
+            customer_id: Uuid::new_v4(),
+            name,
+            contact_channels: Vec::from([]),
+            tier: CustomerTier::Standard,
+            lifetime_value: 0.0,
+            language: "en".to_string(),
+            created_at: Local::now(),
+        }
+    }
+}
+
+// Earlier case of the same customer, as shown to agents
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecentCase {
+    pub case_id: Uuid,
+    pub created_at: DateTime<Local>,
+    pub status: CaseStatus,
+    pub escalated: bool,
+    pub customer_query: String,
+}
+
+// Snapshot of the customer taken when a case is handled
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomerContext {
+    pub name: String,
+    pub tier: CustomerTier,
+    pub lifetime_value: f64,
+    pub language: String,
+    pub recent_cases: Vec<RecentCase>,
+}
+
+impl CustomerContext {
+    pub fn new(customer: &Customer, recent_cases: &[SupportCase]) -> Self {
+        Self {
+            name: customer.name.clone(),
+            tier: customer.tier,
+            lifetime_value: customer.lifetime_value,
+            language: customer.language.clone(),
+            recent_cases: recent_cases
+                .iter()
+                .map(|support_case| RecentCase {
+                    case_id: support_case.case_id,
+                    created_at: support_case.created_at,
+                    status: support_case.status,
+                    escalated: support_case.is_escalated(),
+                    customer_query: support_case.customer_query.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn recent_escalations(&self) -> usize {
+        self.recent_cases
+            .iter()
+            .filter(|recent_case| recent_case.escalated)
+            .count()
+    }
+
+    pub fn prompt_text(&self) -> String {
+        let mut text: String = format!(
+            "Customer '{}' ({} tier, lifetime value {:.2}, language '{}').",
+            self.name, self.tier, self.lifetime_value, self.language
+        );
+        if self.recent_cases.is_empty() {
+            text.push_str(" First contact with support.");
+        } else {
+            text.push_str(&format!(
+                " {} earlier case(s), {} escalated:",
+                self.recent_cases.len(),
+                self.recent_escalations()
+            ));
+            for recent_case in &self.recent_cases {
+                text.push_str(&format!(
+                    " [{} {}] {};",
+                    recent_case.created_at.format("%Y-%m-%d"),
+                    recent_case.status,
+                    recent_case.customer_query
+                ));
+            }
+        }
+        text
+    }
+}
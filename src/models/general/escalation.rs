@@ -0,0 +1,158 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::similarity::text_similarity;
+use crate::helpers::request::extract_json;
+
+pub const UPPER_MANAGEMENT: &str = "upper management";
+
+// Answers that share fewer words with every action are not accepted as that action
+pub const MIN_ACTION_MATCH: f32 = 0.6;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EscalationChoice {
+    Action { action: String },
+    UpperManagement,
+}
+
+impl fmt::Display for EscalationChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Action { action } => write!(f, "{}", action),
+            Self::UpperManagement => write!(f, "{}", UPPER_MANAGEMENT),
+        }
+    }
+}
+
+// Choice of the escalation agent with why it was made and how the answer was matched
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EscalationDecision {
+    pub choice: EscalationChoice,
+    #[serde(default)]
+    pub reason: String,
+    // What the model answered before it was matched against the action list
+    #[serde(default)]
+    pub answer: String,
+    // 1 for an exact match, lower for a fuzzy match
+    #[serde(default)]
+    pub match_score: f32,
+    #[serde(default)]
+    pub attempts: u32,
+    pub decided_at: DateTime<Local>,
+}
+
+// Decision as answered by the model, before validation
+#[derive(Deserialize)]
+struct RawDecision {
+    action: String,
+    #[serde(default)]
+    reason: String,
+}
+
+impl EscalationDecision {
+    pub fn new(choice: EscalationChoice, reason: &str, answer: &str, match_score: f32) -> Self {
+        Self {
+            choice,
+            reason: reason.to_string(),
+            answer: answer.to_string(),
+            match_score,
+            attempts: 1,
+            decided_at: Local::now(),
+        }
+    }
+
+    // Reads '{"action": ..., "reason": ...}' or a bare action, and matches the action
+    // against the supported actions
+    pub fn parse(response: &str, supported_actions: &[String]) -> Result<Self, String> {
+        let (answer, reason): (String, String) =
+            match serde_json::from_str::<RawDecision>(extract_json(response)) {
+                Ok(raw) => (raw.action, raw.reason),
+                Err(_) => (response.to_string(), String::new()),
+            };
+        let normalized: &str = answer.trim().trim_matches(|c: char| !c.is_alphanumeric());
+        if normalized.eq_ignore_ascii_case(UPPER_MANAGEMENT) {
+            return Ok(Self::new(
+                EscalationChoice::UpperManagement,
+                reason.trim(),
+                &answer,
+                1.0,
+            ));
+        }
+        match match_action(normalized, supported_actions) {
+            Some((action, score)) => Ok(Self::new(
+                EscalationChoice::Action { action },
+                reason.trim(),
+                &answer,
+                score,
+            )),
+            None => Err(format!(
+                "'{}' is not one of the available actions",
+                answer.trim()
+            )),
+        }
+    }
+}
+
+// Supported action the answer names, exactly (ignoring case) or by word overlap
+pub fn match_action(answer: &str, supported_actions: &[String]) -> Option<(String, f32)> {
+    if let Some(action) = supported_actions
+        .iter()
+        .find(|action| action.trim().eq_ignore_ascii_case(answer.trim()))
+    {
+        return Some((action.clone(), 1.0));
+    }
+    supported_actions
+        .iter()
+        .map(|action| (action.clone(), text_similarity(answer, action)))
+        .filter(|(_, score)| *score >= MIN_ACTION_MATCH)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_escalation_decision() {
+        let actions: Vec<String> = Vec::from([
+            "Change room".to_string(),
+            "Call mechanic".to_string(),
+            "Provide discount for bar and snacks".to_string(),
+        ]);
+
+        let decision: EscalationDecision = EscalationDecision::parse(
+            r#"{"action": "Call mechanic", "reason": "The air conditioning is broken."}"#,
+            &actions,
+        )
+        .unwrap();
+        assert_eq!(
+            decision.choice,
+            EscalationChoice::Action {
+                action: "Call mechanic".to_string()
+            }
+        );
+        assert_eq!(decision.reason, "The air conditioning is broken.");
+        assert_eq!(decision.match_score, 1.0);
+
+        let fuzzy: EscalationDecision =
+            EscalationDecision::parse("Change the guest room", &actions).unwrap();
+        assert_eq!(fuzzy.choice.to_string(), "Change room");
+        assert!(fuzzy.match_score < 1.0);
+
+        let upper: EscalationDecision =
+            EscalationDecision::parse("Upper Management.", &actions).unwrap();
+        assert_eq!(upper.choice, EscalationChoice::UpperManagement);
+
+        assert!(EscalationDecision::parse("Refund the whole stay", &actions).is_err());
+
+        // Decisions stored before reasons were recorded still load
+        let stored: EscalationDecision = serde_json::from_str(
+            r#"{"choice": {"kind": "upper_management"}, "decided_at": "2024-05-01T10:00:00+02:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(stored.choice, EscalationChoice::UpperManagement);
+        assert_eq!(stored.reason, "");
+    }
+}
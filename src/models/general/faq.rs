@@ -0,0 +1,125 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::similarity::text_similarity;
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_FAQ_PATH: &str = "config/faq.json";
+
+// Canonical answer to a question customers ask in different words
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FaqEntry {
+    pub id: String,
+    // Words of the support context the entry applies to; empty applies to every context
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    pub questions: Vec<String>,
+    pub answer: String,
+}
+
+// Customer message answered from the FAQ instead of the model
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FaqHit {
+    pub faq_id: String,
+    // Question variant the message matched best
+    pub question: String,
+    pub score: f32,
+    pub message_index: usize,
+    pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FaqStore {
+    // Messages matching a question variant less closely are answered by the model
+    pub threshold: f32,
+    pub entries: Vec<FaqEntry>,
+}
+
+impl Default for FaqStore {
+    fn default() -> Self {
+        Self {
+            threshold: 0.75,
+            entries: Vec::from([]),
+        }
+    }
+}
+
+impl FaqEntry {
+    fn applies_to(&self, support_context: &str) -> bool {
+        let support_context: String = support_context.to_lowercase();
+        self.contexts.is_empty()
+            || self
+                .contexts
+                .iter()
+                .any(|context| support_context.contains(&context.to_lowercase()))
+    }
+}
+
+impl FaqStore {
+    // Reads FAQ_PATH, falling back to an empty FAQ
+    pub fn load() -> Self {
+        load_config("FAQ_PATH", DEFAULT_FAQ_PATH)
+    }
+
+    // Entry and question variant matching the message best, if the score reaches the threshold
+    pub fn find(&self, support_context: &str, message: &str) -> Option<(&FaqEntry, &str, f32)> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.applies_to(support_context))
+            .flat_map(|entry| {
+                entry
+                    .questions
+                    .iter()
+                    .map(move |question| (entry, question, text_similarity(message, question)))
+            })
+            .filter(|(_, _, score)| *score >= self.threshold)
+            .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .map(|(entry, question, score)| (entry, question.as_str(), score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_faq_matching() {
+        let store: FaqStore = FaqStore {
+            threshold: 0.75,
+            entries: Vec::from([
+                FaqEntry {
+                    id: "checkout-time".to_string(),
+                    contexts: Vec::from(["hotel".to_string()]),
+                    questions: Vec::from([
+                        "When is checkout?".to_string(),
+                        "What time do I have to leave my room?".to_string(),
+                    ]),
+                    answer: "Checkout is at 11:00.".to_string(),
+                },
+                FaqEntry {
+                    id: "opening-hours".to_string(),
+                    contexts: Vec::from([]),
+                    questions: Vec::from(["What are your opening hours?".to_string()]),
+                    answer: "We are open from 9:00 to 17:00.".to_string(),
+                },
+            ]),
+        };
+
+        let (entry, question, score) = store
+            .find("5 Star Hotel", "what time do I have to leave the room")
+            .unwrap();
+        assert_eq!(entry.answer, "Checkout is at 11:00.");
+        assert_eq!(question, "What time do I have to leave my room?");
+        assert_eq!(score, 1.0);
+
+        // Entries only answer in their contexts, and loose matches go to the model
+        assert!(store.find("Small company", "When is checkout?").is_none());
+        assert_eq!(
+            store.find("Small company", "Opening hours?").unwrap().0.id,
+            "opening-hours"
+        );
+        assert!(store
+            .find("5 Star Hotel", "The air conditioning in my room is broken.")
+            .is_none());
+    }
+}
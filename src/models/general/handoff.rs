@@ -0,0 +1,159 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::escalation::EscalationChoice;
+use super::support_case::SupportCase;
+use super::ticket::{transcript, TranscriptRole};
+use crate::helpers::request::extract_json;
+
+// Summary handed to the person taking over an escalated case
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Handoff {
+    // The customer's issue in one or two sentences
+    pub issue: String,
+    // What the customer and support already said, in order
+    #[serde(default)]
+    pub already_said: Vec<String>,
+    pub sentiment: String,
+    #[serde(default)]
+    pub actions_considered: Vec<String>,
+    pub next_step: String,
+    #[serde(default)]
+    pub open_questions: Vec<String>,
+    // False when the model gave no usable answer and the handoff was built from the case
+    #[serde(default = "default_generated")]
+    pub generated: bool,
+    #[serde(default = "Local::now")]
+    pub created_at: DateTime<Local>,
+}
+
+fn default_generated() -> bool {
+    true
+}
+
+impl Handoff {
+    // Reads the JSON answer of the summarization agent
+    pub fn parse(response: &str) -> Result<Self, String> {
+        let handoff: Handoff = serde_json::from_str(extract_json(response))
+            .map_err(|e| format!("Invalid handoff: {}", e))?;
+        if handoff.issue.trim().is_empty() || handoff.next_step.trim().is_empty() {
+            return Err("Invalid handoff: issue and next step are required".to_string());
+        }
+        Ok(Self {
+            generated: true,
+            created_at: Local::now(),
+            ..handoff
+        })
+    }
+
+    // Handoff from what is recorded on the case, used when the model gives none
+    pub fn from_case(support_case: &SupportCase) -> Self {
+        let already_said: Vec<String> = transcript(support_case)
+            .iter()
+            .map(|entry| match entry.role {
+                TranscriptRole::Customer => format!("Customer: {}", entry.text),
+                TranscriptRole::Support => format!("Support: {}", entry.text),
+            })
+            .collect();
+        let mut actions_considered: Vec<String> = support_case.supported_actions.clone();
+        actions_considered.extend(
+            support_case
+                .action_results
+                .iter()
+                .map(|result| format!("{} ({})", result.action, result.status)),
+        );
+        let next_step: String = match support_case
+            .escalation_decision
+            .as_ref()
+            .map(|decision| &decision.choice)
+        {
+            Some(EscalationChoice::Action { action }) => format!("Follow up on '{}'", action),
+            _ => "Contact the customer and take over the case".to_string(),
+        };
+        Self {
+            issue: support_case.customer_query.clone(),
+            already_said,
+            sentiment: support_case
+                .sentiment
+                .as_ref()
+                .map(|sentiment| sentiment.to_string())
+                .unwrap_or("unknown".to_string()),
+            actions_considered,
+            next_step,
+            open_questions: Vec::from([]),
+            generated: false,
+            created_at: Local::now(),
+        }
+    }
+}
+
+impl fmt::Display for Handoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |items: &[String]| -> String {
+            if items.is_empty() {
+                " -".to_string()
+            } else {
+                items.iter().map(|item| format!("\n- {}", item)).collect()
+            }
+        };
+        write!(
+            f,
+            "Issue: {}\nAlready said:{}\nSentiment: {}\nActions considered:{}\nNext step: {}\nOpen questions:{}",
+            self.issue,
+            list(&self.already_said),
+            self.sentiment,
+            list(&self.actions_considered),
+            self.next_step,
+            list(&self.open_questions)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::escalation::EscalationDecision;
+
+    #[test]
+    fn tests_handoff() {
+        let handoff: Handoff = Handoff::parse(
+            r#"```json
+            {"issue": "Broken air conditioning in room 12.", "already_said": ["Customer: The AC is broken", "Support: A mechanic is on the way"],
+             "sentiment": "negative, frustrated", "actions_considered": ["Call mechanic", "Change room"],
+             "next_step": "Offer a room change tonight.", "open_questions": ["Is a room change acceptable?"]}
+            ```"#,
+        )
+        .unwrap();
+        assert!(handoff.generated);
+        assert_eq!(handoff.actions_considered.len(), 2);
+        assert!(handoff
+            .to_string()
+            .contains("Next step: Offer a room change tonight.\nOpen questions:\n- Is a room change acceptable?"));
+        assert!(
+            Handoff::parse(r#"{"issue": "", "sentiment": "neutral", "next_step": "Call"}"#)
+                .is_err()
+        );
+
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case.supported_actions = Vec::from(["Call mechanic".to_string()]);
+        support_case.escalation_decision = Some(EscalationDecision::new(
+            EscalationChoice::Action {
+                action: "Call mechanic".to_string(),
+            },
+            "AC broken",
+            "Call mechanic",
+            1.0,
+        ));
+        let fallback: Handoff = Handoff::from_case(&support_case);
+        assert!(!fallback.generated);
+        assert_eq!(
+            fallback.already_said,
+            ["Customer: The air conditioning in my room is broken."]
+        );
+        assert_eq!(fallback.next_step, "Follow up on 'Call mechanic'");
+    }
+}
@@ -0,0 +1,241 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::helpers::config::load_config;
+use crate::helpers::request::extract_json;
+
+pub const DEFAULT_INTENT_CONFIG_PATH: &str = "config/intents.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentKind {
+    Question,
+    Complaint,
+    Cancellation,
+    RefundRequest,
+    Compliment,
+}
+
+impl IntentKind {
+    pub const ALL: [IntentKind; 5] = [
+        IntentKind::Question,
+        IntentKind::Complaint,
+        IntentKind::Cancellation,
+        IntentKind::RefundRequest,
+        IntentKind::Compliment,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Question => "question",
+            Self::Complaint => "complaint",
+            Self::Cancellation => "cancellation",
+            Self::RefundRequest => "refund_request",
+            Self::Compliment => "compliment",
+        }
+    }
+}
+
+impl fmt::Display for IntentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Accepts any casing, spaces or dashes, f.e. "Refund request"
+impl FromStr for IntentKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+            .replace([' ', '-'], "_");
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == normalized)
+            .ok_or_else(|| format!("Unknown intent '{}'", value))
+    }
+}
+
+// What the customer wants with their latest message, with the details they gave
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Intent {
+    pub kind: IntentKind,
+    pub confidence: f32,
+    // Details named in the message, f.e. booking_id, product, amount or date
+    pub slots: BTreeMap<String, String>,
+    pub message_index: usize,
+    pub at: DateTime<Local>,
+}
+
+// Intent as answered by the model, before validation
+#[derive(Deserialize)]
+struct RawIntent {
+    intent: String,
+    #[serde(default = "default_confidence")]
+    confidence: f32,
+    #[serde(default)]
+    slots: BTreeMap<String, serde_json::Value>,
+}
+
+fn default_confidence() -> f32 {
+    1.0
+}
+
+impl Intent {
+    // Reads '{"intent": ..., "confidence": ..., "slots": {...}}'; empty slots are dropped
+    pub fn parse(response: &str, message_index: usize) -> Result<Self, String> {
+        let raw: RawIntent = serde_json::from_str(extract_json(response))
+            .map_err(|e| format!("Invalid intent: {}", e))?;
+        let slots: BTreeMap<String, String> = raw
+            .slots
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value: String = match value {
+                    serde_json::Value::String(text) => text.trim().to_string(),
+                    serde_json::Value::Null => String::new(),
+                    other => other.to_string(),
+                };
+                (!value.is_empty()).then_some((name.trim().to_lowercase(), value))
+            })
+            .collect();
+        Ok(Self {
+            kind: raw.intent.parse()?,
+            confidence: raw.confidence.clamp(0.0, 1.0),
+            slots,
+            message_index,
+            at: Local::now(),
+        })
+    }
+
+    // Intent and slots as given to agent prompts
+    pub fn prompt_text(&self) -> String {
+        let slots: Vec<String> = self
+            .slots
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        if slots.is_empty() {
+            self.kind.to_string()
+        } else {
+            format!("{} ({})", self.kind, slots.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2})", self.prompt_text(), self.confidence)
+    }
+}
+
+// How the coordinator handles a case with the intent
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntentRoute {
+    // The agents answer the customer
+    Answer,
+    // The case goes to the escalation agent
+    Escalate,
+    // The action is run right away and the agents answer the customer
+    Action { action: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IntentRule {
+    pub intent: IntentKind,
+    pub route: IntentRoute,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IntentConfig {
+    // Less confident intents are answered
+    pub min_confidence: f32,
+    // Intents without a rule are answered
+    pub rules: Vec<IntentRule>,
+}
+
+impl Default for IntentConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.6,
+            rules: Vec::from([
+                IntentRule {
+                    intent: IntentKind::Cancellation,
+                    route: IntentRoute::Action {
+                        action: "Cancel booking".to_string(),
+                    },
+                },
+                IntentRule {
+                    intent: IntentKind::RefundRequest,
+                    route: IntentRoute::Escalate,
+                },
+            ]),
+        }
+    }
+}
+
+impl IntentConfig {
+    // Reads INTENT_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("INTENT_CONFIG_PATH", DEFAULT_INTENT_CONFIG_PATH)
+    }
+
+    pub fn route_for(&self, intent: &Intent) -> IntentRoute {
+        if intent.confidence < self.min_confidence {
+            return IntentRoute::Answer;
+        }
+        self.rules
+            .iter()
+            .find(|rule| rule.intent == intent.kind)
+            .map(|rule| rule.route.clone())
+            .unwrap_or(IntentRoute::Answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_intent_routing() {
+        let intent: Intent = Intent::parse(
+            r#"```json
+            {"intent": "Cancellation", "confidence": 0.9, "slots": {"Booking_ID": "B-1234", "date": "2024-07-12", "room": null, "nights": 2}}
+            ```"#,
+            0,
+        )
+        .unwrap();
+        assert_eq!(intent.kind, IntentKind::Cancellation);
+        assert_eq!(intent.slots.len(), 3);
+        assert_eq!(intent.slots["booking_id"], "B-1234");
+        assert_eq!(intent.slots["nights"], "2");
+        assert_eq!(
+            intent.prompt_text(),
+            "cancellation (booking_id: B-1234, date: 2024-07-12, nights: 2)"
+        );
+
+        let config: IntentConfig = IntentConfig::default();
+        assert_eq!(
+            config.route_for(&intent),
+            IntentRoute::Action {
+                action: "Cancel booking".to_string()
+            }
+        );
+        let refund: Intent =
+            Intent::parse(r#"{"intent": "refund request", "confidence": 0.8}"#, 1).unwrap();
+        assert_eq!(refund.kind, IntentKind::RefundRequest);
+        assert_eq!(config.route_for(&refund), IntentRoute::Escalate);
+        let unsure: Intent =
+            Intent::parse(r#"{"intent": "refund_request", "confidence": 0.3}"#, 1).unwrap();
+        assert_eq!(config.route_for(&unsure), IntentRoute::Answer);
+        let question: Intent = Intent::parse(r#"{"intent": "question"}"#, 0).unwrap();
+        assert_eq!(config.route_for(&question), IntentRoute::Answer);
+
+        assert!(Intent::parse(r#"{"intent": "greeting"}"#, 0).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use whatlang::Lang;
+
+pub const ENGLISH: &str = "en";
+
+// ISO 639-3 codes reported by the detector mapped to the ISO 639-1 codes used on profiles
+const ISO_639_1: [(&str, &str); 25] = [
+    ("ara", "ar"),
+    ("ces", "cs"),
+    ("cmn", "zh"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("ell", "el"),
+    ("eng", "en"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("heb", "he"),
+    ("hin", "hi"),
+    ("hun", "hu"),
+    ("ita", "it"),
+    ("jpn", "ja"),
+    ("kor", "ko"),
+    ("nld", "nl"),
+    ("nob", "no"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("ron", "ro"),
+    ("rus", "ru"),
+    ("spa", "es"),
+    ("swe", "sv"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageSource {
+    Detected,
+    Profile,
+    Default,
+}
+
+// Language the customer wrote in, which replies are translated to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaseLanguage {
+    pub code: String,
+    pub name: String,
+    pub confidence: f32,
+    pub source: LanguageSource,
+}
+
+impl CaseLanguage {
+    pub fn from_code(code: &str, source: LanguageSource) -> Self {
+        let name: String = ISO_639_1
+            .iter()
+            .find(|(_, short)| short.eq_ignore_ascii_case(code))
+            .and_then(|(long, _)| Lang::from_code(*long))
+            .map(|lang| lang.eng_name().to_string())
+            .unwrap_or(code.to_string());
+        Self {
+            code: code.to_lowercase(),
+            name,
+            confidence: 1.0,
+            source,
+        }
+    }
+
+    pub fn is_english(&self) -> bool {
+        self.code == ENGLISH
+    }
+}
+
+// Language of the text, falling back to the profile language and then English when
+// the text is too short or ambiguous for a reliable detection
+pub fn detect_language(text: &str, profile_language: Option<&str>) -> CaseLanguage {
+    if let Some(info) = whatlang::detect(text).filter(|info| info.is_reliable()) {
+        let code: &str = ISO_639_1
+            .iter()
+            .find(|(long, _)| *long == info.lang().code())
+            .map(|(_, short)| *short)
+            .unwrap_or(info.lang().code());
+        return CaseLanguage {
+            code: code.to_string(),
+            name: info.lang().eng_name().to_string(),
+            confidence: info.confidence() as f32,
+            source: LanguageSource::Detected,
+        };
+    }
+    match profile_language {
+        Some(code) if !code.is_empty() => CaseLanguage::from_code(code, LanguageSource::Profile),
+        _ => CaseLanguage::from_code(ENGLISH, LanguageSource::Default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_detect_language() {
+        let german: CaseLanguage = detect_language(
+            "Die Klimaanlage in meinem Zimmer ist seit gestern Abend kaputt und es ist sehr heiß.",
+            Some("en"),
+        );
+        assert_eq!(german.code, "de");
+        assert_eq!(german.name, "German");
+        assert_eq!(german.source, LanguageSource::Detected);
+
+        let english: CaseLanguage = detect_language(
+            "The air conditioning in my room has been broken since last night.",
+            None,
+        );
+        assert!(english.is_english());
+
+        let short: CaseLanguage = detect_language("ok", Some("fr"));
+        assert_eq!(short.code, "fr");
+        assert_eq!(short.name, "French");
+        assert_eq!(short.source, LanguageSource::Profile);
+
+        assert_eq!(detect_language("ok", None).source, LanguageSource::Default);
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use super::context_budget::ContextTruncation;
+use crate::models::ai::chatgpt::{Message, TokenUsage};
+
+// One model call made while handling a case, as stored in the case trace
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LlmCall {
+    pub agent: String,
+    // Agent instructions the request was built from
+    pub prompt_template: String,
+    // Messages as sent, after fitting them to the context budget
+    pub request: Vec<Message>,
+    // How the request was shortened, if it was over budget
+    #[serde(default)]
+    pub truncation: Option<ContextTruncation>,
+    pub response: Option<String>,
+    // What the agent made of the response, if it had to interpret it
+    pub parsed: Option<Value>,
+    pub model: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub latency_ms: u64,
+    pub usage: Option<TokenUsage>,
+    pub retries: u32,
+    pub errors: Vec<String>,
+}
+
+impl LlmCall {
+    pub fn new(agent: &str, prompt_template: &str, request: Vec<Message>) -> Self {
+        Self {
+            agent: agent.to_string(),
+            prompt_template: prompt_template.to_string(),
+            request,
+            truncation: None,
+            response: None,
+            parsed: None,
+            model: None,
+            started_at: Local::now(),
+            latency_ms: 0,
+            usage: None,
+            retries: 0,
+            errors: Vec::from([]),
+        }
+    }
+
+    // Errors of the call as one line, used when an agent has to report a failed call
+    pub fn error_text(&self) -> String {
+        if self.errors.is_empty() {
+            "No response from model".to_string()
+        } else {
+            self.errors.join("; ")
+        }
+    }
+}
+
+// Traces written before calls were recorded only held the plain prompt and reply messages
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TraceEntry {
+    Call(Box<LlmCall>),
+    Message(Message),
+}
+
+// Reads both trace formats, pairing legacy replies with the prompt they answered
+pub fn deserialize_trace<'de, D>(deserializer: D) -> Result<Vec<LlmCall>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries: Vec<TraceEntry> = Vec::deserialize(deserializer)?;
+    let mut trace: Vec<LlmCall> = Vec::from([]);
+    for entry in entries {
+        match entry {
+            TraceEntry::Call(call) => trace.push(*call),
+            TraceEntry::Message(message) if message.role == "assistant" => {
+                match trace.last_mut() {
+                    Some(call) if call.response.is_none() => call.response = Some(message.content),
+                    _ => {
+                        let mut call: LlmCall = LlmCall::new("unknown", "", Vec::from([]));
+                        call.response = Some(message.content);
+                        trace.push(call);
+                    }
+                }
+            }
+            TraceEntry::Message(message) => {
+                trace.push(LlmCall::new("unknown", "", Vec::from([message])))
+            }
+        }
+    }
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct Traced {
+        #[serde(deserialize_with = "deserialize_trace")]
+        trace: Vec<LlmCall>,
+    }
+
+    #[test]
+    fn tests_deserialize_legacy_trace() {
+        let call: LlmCall = LlmCall::new("Psychologist", "Classify the sentiment", Vec::from([]));
+        let traced: Traced = serde_json::from_value(json!({
+            "trace": [
+                { "role": "system", "content": "Answer the guest" },
+                { "role": "assistant", "content": "We are on it." },
+                { "role": "system", "content": "Classify the sentiment" },
+                call
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(traced.trace.len(), 3);
+        assert_eq!(traced.trace[0].agent, "unknown");
+        assert_eq!(traced.trace[0].request[0].content, "Answer the guest");
+        assert_eq!(traced.trace[0].response.as_deref(), Some("We are on it."));
+        assert_eq!(traced.trace[1].response, None);
+        assert_eq!(traced.trace[2].agent, "Psychologist");
+    }
+}
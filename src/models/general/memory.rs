@@ -0,0 +1,175 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::helpers::config::load_config;
+use crate::models::ai::chatgpt::Message;
+
+pub const DEFAULT_MEMORY_CONFIG_PATH: &str = "config/memory.json";
+
+// Characters of each old entry kept when old entries are folded without the model
+const FALLBACK_ENTRY_CHARS: usize = 120;
+
+// Cuts text to a number of characters, marking the cut with "..."
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!("{}...", kept.trim_end())
+}
+
+// What one agent remembers of its exchanges with the customer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentMemory {
+    pub role: String,
+    // Summary of entries that no longer fit
+    #[serde(default)]
+    pub summary: Option<String>,
+    // Customer messages ("user") and the agent's answers ("assistant"), oldest first
+    pub entries: Vec<Message>,
+    // Case the memory was carried over from
+    #[serde(default)]
+    pub carried_from: Option<Uuid>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl AgentMemory {
+    pub fn new(role: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            summary: None,
+            entries: Vec::from([]),
+            carried_from: None,
+            updated_at: Local::now(),
+        }
+    }
+
+    pub fn remember(&mut self, role: &str, content: &str) {
+        self.entries.push(Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        });
+        self.updated_at = Local::now();
+    }
+
+    // Messages put in front of the agent's request: the summary, then the entries
+    pub fn messages(&self) -> Vec<Message> {
+        let mut messages: Vec<Message> = Vec::from([]);
+        if let Some(summary) = &self.summary {
+            messages.push(Message {
+                role: "system".to_string(),
+                content: format!("WHAT YOU REMEMBER FROM EARLIER: {}", summary),
+            });
+        }
+        messages.extend(self.entries.iter().cloned());
+        messages
+    }
+
+    // Takes out the entries that exceed the limit, keeping the most recent ones
+    pub fn overflow(&mut self, config: &MemoryConfig) -> Vec<Message> {
+        for entry in &mut self.entries {
+            entry.content = truncate(&entry.content, config.max_entry_chars);
+        }
+        if self.entries.len() <= config.max_entries {
+            return Vec::from([]);
+        }
+        let keep: usize = config.keep_recent.min(config.max_entries);
+        self.entries.drain(..self.entries.len() - keep).collect()
+    }
+
+    // Replaces the summary with one that also covers the entries taken out
+    pub fn fold(&mut self, summary: &str, config: &MemoryConfig) {
+        self.summary = Some(truncate(summary.trim(), config.max_summary_chars));
+        self.updated_at = Local::now();
+    }
+
+    // Summary without the model: the earlier summary plus the start of every old entry
+    pub fn fallback_summary(&self, old_entries: &[Message]) -> String {
+        let mut parts: Vec<String> = self.summary.iter().cloned().collect();
+        parts.extend(old_entries.iter().map(|entry| {
+            let speaker: &str = if entry.role == "assistant" {
+                "You"
+            } else {
+                "Customer"
+            };
+            format!(
+                "{}: {}",
+                speaker,
+                truncate(&entry.content, FALLBACK_ENTRY_CHARS)
+            )
+        }));
+        parts.join(" | ")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MemoryConfig {
+    // Entries an agent keeps before old ones are summarized
+    pub max_entries: usize,
+    // Entries kept as they are when old ones are summarized
+    pub keep_recent: usize,
+    pub max_entry_chars: usize,
+    pub max_summary_chars: usize,
+    // New cases start with the memory of the customer's latest case
+    pub carry_over: bool,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 12,
+            keep_recent: 6,
+            max_entry_chars: 800,
+            max_summary_chars: 1500,
+            carry_over: true,
+        }
+    }
+}
+
+impl MemoryConfig {
+    // Reads MEMORY_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("MEMORY_CONFIG_PATH", DEFAULT_MEMORY_CONFIG_PATH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_agent_memory() {
+        let config: MemoryConfig = MemoryConfig {
+            max_entries: 4,
+            keep_recent: 2,
+            max_entry_chars: 20,
+            max_summary_chars: 1500,
+            carry_over: true,
+        };
+        let mut memory: AgentMemory = AgentMemory::new("Escalation Manager");
+        memory.remember("user", "The air conditioning is broken");
+        memory.remember("assistant", "Call mechanic");
+        memory.remember("user", "Still broken");
+        memory.remember("assistant", "Provide discount for bar and snacks");
+        assert!(memory.overflow(&config).is_empty());
+        assert_eq!(memory.entries[3].content, "Provide discount for...");
+
+        memory.remember("user", "Nothing happened!");
+        let old: Vec<Message> = memory.overflow(&config);
+        assert_eq!(old.len(), 3);
+        assert_eq!(memory.entries.len(), 2);
+        assert_eq!(memory.entries[0].content, "Provide discount for...");
+
+        let summary: String = memory.fallback_summary(&old);
+        assert_eq!(
+            summary,
+            "Customer: The air conditioning... | You: Call mechanic | Customer: Still broken"
+        );
+        memory.fold(&summary, &config);
+        let messages: Vec<Message> = memory.messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, "system");
+        assert!(messages[0].content.ends_with("Customer: Still broken"));
+    }
+}
@@ -1,2 +1,25 @@
+pub mod action;
+pub mod attachment;
+pub mod audit;
+pub mod case_export;
+pub mod case_status;
+pub mod context_budget;
+pub mod customer;
+pub mod escalation;
+pub mod faq;
+pub mod handoff;
+pub mod intent;
+pub mod language;
+pub mod llm_call;
+pub mod memory;
+pub mod notification;
+pub mod policy;
+pub mod priority;
+pub mod review;
+pub mod sentiment;
+pub mod similarity;
+pub mod sla;
 pub mod support_case;
+pub mod taxonomy;
+pub mod ticket;
 pub mod route;
\ No newline at end of file
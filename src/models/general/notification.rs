@@ -0,0 +1,204 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use super::priority::Priority;
+use super::support_case::SupportCase;
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_NOTIFICATION_CONFIG_PATH: &str = "config/notifications.json";
+
+const DEFAULT_SUBJECT_TEMPLATE: &str = "[{priority}] Upper management needed for case {case_id}";
+
+const DEFAULT_BODY_TEMPLATE: &str = "A {priority} case in '{context}' needs upper management.
+
+Case: {case_id}
+Status: {status}
+Customer: {customer}
+Sentiment: {sentiment}
+Decision: {decision}
+
+Handoff:
+{handoff}
+
+Customer message:
+{message}
+
+Latest reply:
+{response}
+
+Tickets: {tickets}";
+
+// Message to upper management, rendered from the templates
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Notification {
+    pub case_id: Uuid,
+    pub priority: Priority,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Outcome of sending one notification over one channel, stored on the case
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationDelivery {
+    pub channel: String,
+    pub subject: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    // Error of the last failed attempt
+    pub error: Option<String>,
+    pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    Email {
+        host: String,
+        port: u16,
+        // Upgrade the connection with STARTTLS; off for local SMTP sinks
+        #[serde(default)]
+        starttls: bool,
+        // Environment variables holding the SMTP login, if the server needs one
+        #[serde(default)]
+        username_env: Option<String>,
+        #[serde(default)]
+        password_env: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+    // Posts the notification as JSON
+    Webhook {
+        url: String,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationConfig {
+    pub channels: Vec<ChannelConfig>,
+    pub max_attempts: u32,
+    // Wait before the second attempt, doubled for every further attempt
+    pub retry_delay_ms: u64,
+    pub subject_template: String,
+    pub body_template: String,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            channels: Vec::from([]),
+            max_attempts: 3,
+            retry_delay_ms: 500,
+            subject_template: DEFAULT_SUBJECT_TEMPLATE.to_string(),
+            body_template: DEFAULT_BODY_TEMPLATE.to_string(),
+        }
+    }
+}
+
+// Fills '{placeholder}'s with the case summary; unknown placeholders are kept as they are
+pub fn render_template(template: &str, support_case: &SupportCase) -> String {
+    let customer: String = support_case
+        .customer
+        .as_ref()
+        .map(|customer| format!("{} ({})", customer.name, customer.tier))
+        .unwrap_or("unknown".to_string());
+    let decision: String = support_case
+        .escalation_decision
+        .as_ref()
+        .map(|decision| format!("{} ({})", decision.choice, decision.reason))
+        .unwrap_or("-".to_string());
+    let tickets: Vec<String> = support_case
+        .tickets
+        .iter()
+        .map(|ticket| format!("{} ({})", ticket.key, ticket.location))
+        .collect();
+    let handoff: String = support_case
+        .handoff
+        .as_ref()
+        .map(|handoff| handoff.to_string())
+        .unwrap_or("-".to_string());
+    let values: [(&str, String); 12] = [
+        ("case_id", support_case.case_id.to_string()),
+        ("priority", support_case.priority.to_string()),
+        ("status", support_case.status.to_string()),
+        ("context", support_case.support_context.clone()),
+        ("customer", customer),
+        (
+            "sentiment",
+            support_case
+                .sentiment
+                .as_ref()
+                .map(|sentiment| sentiment.to_string())
+                .unwrap_or("-".to_string()),
+        ),
+        ("decision", decision),
+        ("handoff", handoff),
+        ("query", support_case.customer_query.clone()),
+        (
+            "message",
+            support_case.latest_customer_message().to_string(),
+        ),
+        (
+            "response",
+            support_case
+                .original_response
+                .as_ref()
+                .or(support_case.support_response.as_ref())
+                .cloned()
+                .unwrap_or("-".to_string()),
+        ),
+        (
+            "tickets",
+            if tickets.is_empty() {
+                "-".to_string()
+            } else {
+                tickets.join(", ")
+            },
+        ),
+    ];
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+impl NotificationConfig {
+    // Reads NOTIFICATION_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("NOTIFICATION_CONFIG_PATH", DEFAULT_NOTIFICATION_CONFIG_PATH)
+    }
+
+    pub fn notification_for(&self, support_case: &SupportCase) -> Notification {
+        Notification {
+            case_id: support_case.case_id,
+            priority: support_case.priority,
+            subject: render_template(&self.subject_template, support_case),
+            body: render_template(&self.body_template, support_case),
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use chrono::{DateTime, Local};
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use super::case_status::CaseStatus;
+use super::support_case::SupportCase;
+use crate::helpers::command_line::CLIPrint;
+use crate::helpers::config::read_config;
+
+pub const DEFAULT_POLICY_CONFIG_PATH: &str = "config/policies.json";
+
+// Sentences with these words offer compensation, so amounts in them are checked
+const COMPENSATION_WORDS: [&str; 18] = [
+    "refund",
+    "refunds",
+    "refunded",
+    "discount",
+    "discounts",
+    "discounted",
+    "compensate",
+    "compensated",
+    "compensation",
+    "voucher",
+    "vouchers",
+    "credit",
+    "credits",
+    "credited",
+    "reimburse",
+    "reimbursed",
+    "reimbursement",
+    "off",
+];
+
+const CURRENCY_SYMBOLS: [char; 3] = ['$', '€', '£'];
+
+const CURRENCY_WORDS: [&str; 8] = [
+    "eur", "euro", "euros", "usd", "dollar", "dollars", "gbp", "pounds",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyTarget {
+    Reply,
+    Action,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    Compensation,
+    Commitment,
+    Phrase,
+    Disclaimer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationOutcome {
+    // The reply was replaced and a person takes over
+    Blocked,
+    // The action was not run and upper management decides instead
+    Escalated,
+    // The missing disclaimer was added to the reply
+    Fixed,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Compensation => "compensation",
+            Self::Commitment => "commitment",
+            Self::Phrase => "phrase",
+            Self::Disclaimer => "disclaimer",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl fmt::Display for ViolationOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Blocked => "blocked",
+            Self::Escalated => "escalated",
+            Self::Fixed => "fixed",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Policy rule a reply or action broke, and what was done about it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PolicyViolation {
+    pub policy: String,
+    pub target: PolicyTarget,
+    pub kind: ViolationKind,
+    pub detail: String,
+    pub outcome: ViolationOutcome,
+    pub at: DateTime<Local>,
+}
+
+// Guardrails for the support contexts matching 'contexts'
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContextPolicy {
+    pub name: String,
+    // Words of the support context the policy applies to; empty applies to every context
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    // Highest amount of money that may be offered
+    #[serde(default)]
+    pub max_compensation: Option<f64>,
+    #[serde(default)]
+    pub max_discount_percent: Option<f64>,
+    #[serde(default)]
+    pub forbidden_commitments: Vec<String>,
+    #[serde(default)]
+    pub mandatory_disclaimers: Vec<String>,
+    #[serde(default)]
+    pub forbidden_phrases: Vec<String>,
+}
+
+// Amounts of money (false) and percentages (true) mentioned in the text
+fn amounts(text: &str) -> Vec<(f64, bool)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut found: Vec<(f64, bool)> = Vec::from([]);
+    let mut index: usize = 0;
+    while index < chars.len() {
+        if !chars[index].is_ascii_digit() {
+            index += 1;
+            continue;
+        }
+        let start: usize = index;
+        while index < chars.len()
+            && (chars[index].is_ascii_digit() || matches!(chars[index], '.' | ','))
+        {
+            index += 1;
+        }
+        let number: String = chars[start..index]
+            .iter()
+            .filter(|c| **c != ',')
+            .collect::<String>()
+            .trim_end_matches('.')
+            .to_string();
+        let Ok(value) = number.parse::<f64>() else {
+            continue;
+        };
+        let before: Option<char> = chars[..start]
+            .iter()
+            .rev()
+            .find(|c| !c.is_whitespace())
+            .copied();
+        let after: String = chars[index..]
+            .iter()
+            .collect::<String>()
+            .trim_start()
+            .to_lowercase();
+        let next_word: &str = after
+            .split(|c: char| !c.is_alphabetic())
+            .next()
+            .unwrap_or("");
+        if after.starts_with('%') || next_word == "percent" {
+            found.push((value, true));
+        } else if before.is_some_and(|c| CURRENCY_SYMBOLS.contains(&c))
+            || after.starts_with(CURRENCY_SYMBOLS)
+            || CURRENCY_WORDS.contains(&next_word)
+        {
+            found.push((value, false));
+        }
+    }
+    found
+}
+
+// Splits at sentence ends, keeping decimals like '12.50' together
+fn sentences(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut sentences: Vec<String> = Vec::from([]);
+    let mut current: String = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        current.push(*c);
+        let ends: bool = matches!(c, '!' | '?' | '\n')
+            || (*c == '.' && chars.get(index + 1).is_none_or(|next| next.is_whitespace()));
+        if ends {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
+
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    !phrase.trim().is_empty() && text.to_lowercase().contains(&phrase.trim().to_lowercase())
+}
+
+impl ContextPolicy {
+    pub fn applies_to(&self, support_context: &str) -> bool {
+        self.contexts.is_empty()
+            || self
+                .contexts
+                .iter()
+                .any(|context| contains_phrase(support_context, context))
+    }
+
+    // Compensation, commitment and phrase rules the text breaks
+    pub fn check(&self, text: &str) -> Vec<(ViolationKind, String)> {
+        let mut violations: Vec<(ViolationKind, String)> = Vec::from([]);
+        for sentence in sentences(text) {
+            let lower: String = sentence.to_lowercase();
+            let offers: bool = lower
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|token| COMPENSATION_WORDS.contains(&token));
+            if !offers {
+                continue;
+            }
+            for (value, percent) in amounts(&sentence) {
+                let (limit, unit): (Option<f64>, &str) = if percent {
+                    (self.max_discount_percent, "%")
+                } else {
+                    (self.max_compensation, "")
+                };
+                if let Some(limit) = limit.filter(|limit| value > *limit) {
+                    violations.push((
+                        ViolationKind::Compensation,
+                        format!(
+                            "Offers {}{} where at most {}{} is allowed",
+                            value, unit, limit, unit
+                        ),
+                    ));
+                }
+            }
+        }
+        for commitment in &self.forbidden_commitments {
+            if contains_phrase(text, commitment) {
+                violations.push((
+                    ViolationKind::Commitment,
+                    format!("Commits to '{}'", commitment),
+                ));
+            }
+        }
+        for phrase in &self.forbidden_phrases {
+            if contains_phrase(text, phrase) {
+                violations.push((ViolationKind::Phrase, format!("Uses '{}'", phrase)));
+            }
+        }
+        violations
+    }
+
+    pub fn missing_disclaimers(&self, text: &str) -> Vec<String> {
+        self.mandatory_disclaimers
+            .iter()
+            .filter(|disclaimer| !contains_phrase(text, disclaimer))
+            .cloned()
+            .collect()
+    }
+
+    fn violation(
+        &self,
+        target: PolicyTarget,
+        kind: ViolationKind,
+        detail: String,
+        outcome: ViolationOutcome,
+    ) -> PolicyViolation {
+        PolicyViolation {
+            policy: self.name.clone(),
+            target,
+            kind,
+            detail,
+            outcome,
+            at: Local::now(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PolicyConfig {
+    // First matching policy applies
+    pub policies: Vec<ContextPolicy>,
+    // Sent to the customer instead of a blocked reply
+    pub blocked_reply: String,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            policies: Vec::from([ContextPolicy {
+                name: "default".to_string(),
+                contexts: Vec::from([]),
+                max_compensation: Some(0.0),
+                max_discount_percent: Some(0.0),
+                forbidden_commitments: Vec::from([
+                    "full refund".to_string(),
+                    "we guarantee".to_string(),
+                ]),
+                mandatory_disclaimers: Vec::from([]),
+                forbidden_phrases: Vec::from([]),
+            }]),
+            blocked_reply:
+                "Thank you for your message. A member of our team will get back to you shortly."
+                    .to_string(),
+        }
+    }
+}
+
+impl PolicyConfig {
+    // Built-in defaults only stand in for a missing file; a broken one is an error
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        if !Path::new(path).exists() {
+            CLIPrint::Warning.out(
+                "System",
+                format!("Using default policy config ({} not found)", path).as_str(),
+            );
+            return Ok(Self::default());
+        }
+        read_config(path).map_err(|e| format!("Invalid policy config {}: {}", path, e).into())
+    }
+
+    // Reads POLICY_CONFIG_PATH; guardrails are never dropped because of a malformed file
+    pub fn load() -> Self {
+        dotenv().ok();
+
+        let path: String =
+            dotenv::var("POLICY_CONFIG_PATH").unwrap_or(DEFAULT_POLICY_CONFIG_PATH.to_string());
+        Self::from_file(&path).expect("Should have read policy config")
+    }
+
+    pub fn policy_for(&self, support_context: &str) -> Option<&ContextPolicy> {
+        self.policies
+            .iter()
+            .find(|policy| policy.applies_to(support_context))
+    }
+
+    // Blocks a reply that breaks the policy and escalates the case; missing disclaimers are added.
+    // Translated replies got their disclaimers before translation, so only the rules are checked
+    pub fn enforce_reply(&self, support_case: &mut SupportCase) -> Vec<PolicyViolation> {
+        let Some(policy) = self.policy_for(&support_case.support_context) else {
+            return Vec::from([]);
+        };
+        let Some(reply) = support_case.support_response.clone() else {
+            return Vec::from([]);
+        };
+        let translated: bool = support_case.original_response.is_some();
+        let mut violations: Vec<PolicyViolation> = policy
+            .check(&reply)
+            .into_iter()
+            .map(|(kind, detail)| {
+                policy.violation(PolicyTarget::Reply, kind, detail, ViolationOutcome::Blocked)
+            })
+            .collect();
+        let mut enforced: String = if violations.is_empty() {
+            reply
+        } else {
+            // The blocked reply replaces the English original as well
+            support_case.original_response = None;
+            self.blocked_reply.clone()
+        };
+        if let Some(first) = violations.first() {
+            let reason: String = format!("Policy violation: {}", first.detail);
+            if support_case.status != CaseStatus::Escalated {
+                if let Err(e) = support_case.transition(CaseStatus::Escalated, "Policy", &reason) {
+                    CLIPrint::Warning.out("Policy", e.to_string().as_str());
+                }
+            }
+        }
+        for disclaimer in policy
+            .missing_disclaimers(&enforced)
+            .into_iter()
+            .filter(|_| !translated)
+        {
+            enforced = format!("{}\n\n{}", enforced.trim_end(), disclaimer);
+            violations.push(policy.violation(
+                PolicyTarget::Reply,
+                ViolationKind::Disclaimer,
+                format!("Missing '{}'", disclaimer),
+                ViolationOutcome::Fixed,
+            ));
+        }
+        if !violations.is_empty() {
+            support_case.support_response = Some(enforced);
+            support_case
+                .policy_violations
+                .extend(violations.iter().cloned());
+            support_case.updated();
+        }
+        violations
+    }
+
+    // Rules the escalation action breaks; such actions go to upper management instead
+    pub fn check_action(
+        &self,
+        support_case: &mut SupportCase,
+        action: &str,
+    ) -> Vec<PolicyViolation> {
+        let Some(policy) = self.policy_for(&support_case.support_context) else {
+            return Vec::from([]);
+        };
+        let violations: Vec<PolicyViolation> = policy
+            .check(action)
+            .into_iter()
+            .map(|(kind, detail)| {
+                policy.violation(
+                    PolicyTarget::Action,
+                    kind,
+                    detail,
+                    ViolationOutcome::Escalated,
+                )
+            })
+            .collect();
+        if !violations.is_empty() {
+            support_case
+                .policy_violations
+                .extend(violations.iter().cloned());
+            support_case.updated();
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_policy_enforcement() {
+        let config: PolicyConfig = PolicyConfig {
+            policies: Vec::from([
+                ContextPolicy {
+                    name: "hotel".to_string(),
+                    contexts: Vec::from(["hotel".to_string()]),
+                    max_compensation: Some(50.0),
+                    max_discount_percent: Some(20.0),
+                    forbidden_commitments: Vec::from(["free suite".to_string()]),
+                    mandatory_disclaimers: Vec::from([
+                        "Offers are subject to availability.".to_string()
+                    ]),
+                    forbidden_phrases: Vec::from(["calm down".to_string()]),
+                },
+                ContextPolicy {
+                    name: "default".to_string(),
+                    contexts: Vec::from([]),
+                    max_compensation: None,
+                    max_discount_percent: None,
+                    forbidden_commitments: Vec::from([]),
+                    mandatory_disclaimers: Vec::from([]),
+                    forbidden_phrases: Vec::from([]),
+                },
+            ]),
+            ..Default::default()
+        };
+        let hotel: &ContextPolicy = config.policy_for("5 Star Hotel").unwrap();
+        assert_eq!(hotel.name, "hotel");
+        assert_eq!(config.policy_for("Small company").unwrap().name, "default");
+
+        // Amounts only count in sentences offering compensation
+        assert!(hotel
+            .check("Your room 404 costs $300 per night.")
+            .is_empty());
+        assert!(hotel.check("We refund €30.50 of your stay.").is_empty());
+        // Only whole words offer compensation
+        assert!(hotel.check("Our office shuttle costs 80 EUR.").is_empty());
+        assert_eq!(hotel.check("Take 30% off your next stay.").len(), 1);
+        let kinds: Vec<ViolationKind> = hotel
+            .check("Please calm down. We offer a 50% discount and a refund of 120 EUR, plus a free suite!")
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ViolationKind::Compensation,
+                ViolationKind::Compensation,
+                ViolationKind::Commitment,
+                ViolationKind::Phrase
+            ]
+        );
+
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case.support_response = Some("A mechanic is on the way.".to_string());
+        let fixed: Vec<PolicyViolation> = config.enforce_reply(&mut support_case);
+        assert_eq!(fixed[0].outcome, ViolationOutcome::Fixed);
+        assert_eq!(
+            support_case.support_response.as_deref(),
+            Some("A mechanic is on the way.\n\nOffers are subject to availability.")
+        );
+        assert_eq!(support_case.status, CaseStatus::New);
+
+        support_case.support_response = Some("Enjoy a free suite tonight.".to_string());
+        let blocked: Vec<PolicyViolation> = config.enforce_reply(&mut support_case);
+        assert_eq!(blocked[0].outcome, ViolationOutcome::Blocked);
+        assert!(support_case
+            .support_response
+            .as_deref()
+            .unwrap()
+            .starts_with(&config.blocked_reply));
+        assert_eq!(support_case.status, CaseStatus::Escalated);
+
+        // Translated replies are checked again without adding English disclaimers
+        support_case.original_response = Some("We will refund 20 EUR.".to_string());
+        support_case.support_response = Some("Wir erstatten 20 EUR.".to_string());
+        assert!(config.enforce_reply(&mut support_case).is_empty());
+        support_case.support_response = Some("Wir schenken Ihnen eine free suite.".to_string());
+        assert_eq!(
+            config.enforce_reply(&mut support_case)[0].outcome,
+            ViolationOutcome::Blocked
+        );
+        assert_eq!(support_case.original_response, None);
+
+        assert_eq!(
+            config
+                .check_action(&mut support_case, "Provide 30% discount for bar")
+                .len(),
+            1
+        );
+        assert!(config
+            .check_action(&mut support_case, "Call mechanic")
+            .is_empty());
+        assert_eq!(support_case.policy_violations.len(), 5);
+    }
+
+    #[test]
+    fn tests_policy_config_file() {
+        let missing: PolicyConfig = PolicyConfig::from_file("config/does-not-exist.json").unwrap();
+        assert_eq!(missing, PolicyConfig::default());
+
+        let path = std::env::temp_dir().join(format!("policies-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{ "policies": [ { "name": "hotel" "#).unwrap();
+        assert!(PolicyConfig::from_file(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
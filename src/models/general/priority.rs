@@ -0,0 +1,196 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::helpers::config::load_config;
+
+use super::customer::CustomerTier;
+use super::sentiment::Polarity;
+use super::support_case::SupportCase;
+
+pub const DEFAULT_PRIORITY_CONFIG_PATH: &str = "config/priority.json";
+
+// P1 is the most urgent; the derived ordering sorts P1 first
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    P1,
+    P2,
+    P3,
+    #[default]
+    P4,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PriorityWeights {
+    pub sentiment: f32,
+    pub urgency: f32,
+    pub customer_tier: f32,
+    pub case_age: f32,
+}
+
+// Minimum score for each priority; anything below p3 is P4
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PriorityThresholds {
+    pub p1: f32,
+    pub p2: f32,
+    pub p3: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PriorityConfig {
+    pub weights: PriorityWeights,
+    pub thresholds: PriorityThresholds,
+    pub urgency_keywords: Vec<String>,
+    // Age at which a case contributes its full age weight
+    pub max_age_hours: f32,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            weights: PriorityWeights {
+                sentiment: 0.35,
+                urgency: 0.35,
+                customer_tier: 0.2,
+                case_age: 0.1,
+            },
+            thresholds: PriorityThresholds {
+                p1: 0.7,
+                p2: 0.5,
+                p3: 0.3,
+            },
+            urgency_keywords: [
+                "urgent", "immediately", "asap", "emergency", "now", "lawyer", "refund",
+                "dangerous", "broken",
+            ]
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .collect(),
+            max_age_hours: 48.0,
+        }
+    }
+}
+
+impl PriorityConfig {
+    // Reads PRIORITY_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("PRIORITY_CONFIG_PATH", DEFAULT_PRIORITY_CONFIG_PATH)
+    }
+
+    // Negative sentiment raises the score by its intensity, pulled towards neutral when the
+    // model was unsure
+    fn sentiment_score(&self, support_case: &SupportCase) -> f32 {
+        let Some(sentiment) = &support_case.sentiment else {
+            return 0.5;
+        };
+        let score: f32 = match sentiment.polarity {
+            Polarity::Negative => 0.5 + 0.5 * sentiment.intensity,
+            Polarity::Mixed => 0.5 + 0.25 * sentiment.intensity,
+            Polarity::Neutral => 0.5,
+            Polarity::Positive => 0.5 - 0.5 * sentiment.intensity,
+        };
+        0.5 + (score - 0.5) * sentiment.confidence
+    }
+
+    fn urgency_score(&self, support_case: &SupportCase) -> f32 {
+        let query: String = support_case.customer_query.to_lowercase();
+        let words: Vec<&str> = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let matches: usize = self
+            .urgency_keywords
+            .iter()
+            .filter(|keyword| words.contains(&keyword.to_lowercase().as_str()))
+            .count();
+        (matches as f32 / 2.0).min(1.0)
+    }
+
+    fn tier_score(&self, support_case: &SupportCase) -> f32 {
+        match support_case.customer_tier {
+            CustomerTier::Standard => 0.0,
+            CustomerTier::Premium => 0.5,
+            CustomerTier::Vip => 1.0,
+        }
+    }
+
+    fn age_score(&self, support_case: &SupportCase, now: DateTime<Local>) -> f32 {
+        let age_hours: f32 = (now - support_case.created_at).num_minutes().max(0) as f32 / 60.0;
+        if self.max_age_hours <= 0.0 {
+            return 1.0;
+        }
+        (age_hours / self.max_age_hours).min(1.0)
+    }
+
+    // Weighted score between 0 and 1
+    pub fn score(&self, support_case: &SupportCase, now: DateTime<Local>) -> f32 {
+        let weights: &PriorityWeights = &self.weights;
+        let total_weight: f32 =
+            weights.sentiment + weights.urgency + weights.customer_tier + weights.case_age;
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+        let weighted: f32 = weights.sentiment * self.sentiment_score(support_case)
+            + weights.urgency * self.urgency_score(support_case)
+            + weights.customer_tier * self.tier_score(support_case)
+            + weights.case_age * self.age_score(support_case, now);
+        weighted / total_weight
+    }
+
+    pub fn priority_for_score(&self, score: f32) -> Priority {
+        if score >= self.thresholds.p1 {
+            Priority::P1
+        } else if score >= self.thresholds.p2 {
+            Priority::P2
+        } else if score >= self.thresholds.p3 {
+            Priority::P3
+        } else {
+            Priority::P4
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::sentiment::Sentiment;
+    use chrono::Duration;
+
+    #[test]
+    fn tests_priority_scoring() {
+        let config: PriorityConfig = PriorityConfig::default();
+        let now: DateTime<Local> = Local::now();
+
+        let mut calm: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "Could I get an extra pillow?".to_string(),
+        );
+        calm.sentiment = Some(Sentiment::new(Polarity::Positive));
+        assert_eq!(config.priority_for_score(config.score(&calm, now)), Priority::P4);
+
+        let mut angry_vip: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The heater is broken, fix it immediately!".to_string(),
+        );
+        angry_vip.sentiment = Some(Sentiment::new(Polarity::Negative));
+        angry_vip.customer_tier = CustomerTier::Vip;
+        assert_eq!(config.priority_for_score(config.score(&angry_vip, now)), Priority::P1);
+
+        // Waiting long enough raises priority on its own
+        let mut waiting: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The heater is broken.".to_string(),
+        );
+        waiting.sentiment = Some(Sentiment::new(Polarity::Negative));
+        let fresh: f32 = config.score(&waiting, now);
+        waiting.created_at = now - Duration::hours(72);
+        assert!(config.score(&waiting, now) > fresh);
+        assert!(Priority::P1 < Priority::P4);
+    }
+}
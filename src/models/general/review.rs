@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::helpers::config::load_config;
+use crate::helpers::request::extract_json;
+
+pub const DEFAULT_REVIEW_CONFIG_PATH: &str = "config/review.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewVerdict {
+    // The draft goes out as it is
+    Approve,
+    // The reviewer's version goes out instead of the draft
+    Rewrite,
+    // Nothing of the draft goes out and the case is escalated
+    Block,
+}
+
+impl fmt::Display for ReviewVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Approve => "approve",
+            Self::Rewrite => "rewrite",
+            Self::Block => "block",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Review of a draft reply, before it reaches the customer
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseReview {
+    pub verdict: ReviewVerdict,
+    // Tone, policy or grounding problems found in the draft
+    #[serde(default)]
+    pub issues: Vec<String>,
+    // Reply to send instead of the draft, for rewrites
+    #[serde(default)]
+    pub rewritten: Option<String>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl ResponseReview {
+    // Reads the reviewer's JSON answer; rewrites without a new reply are invalid
+    pub fn parse(response: &str) -> Result<Self, String> {
+        let mut review: ResponseReview = serde_json::from_str(extract_json(response))
+            .map_err(|e| format!("Invalid review: {}", e))?;
+        review.rewritten = review
+            .rewritten
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        match review.verdict {
+            ReviewVerdict::Rewrite if review.rewritten.is_none() => {
+                Err("Invalid review: a rewrite needs the rewritten reply".to_string())
+            }
+            ReviewVerdict::Rewrite => Ok(review),
+            ReviewVerdict::Approve | ReviewVerdict::Block => Ok(Self {
+                rewritten: None,
+                ..review
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReviewConfig {
+    pub tone_guidelines: Vec<String>,
+    pub policy_constraints: Vec<String>,
+    // Sent to the customer instead of a blocked draft
+    pub blocked_reply: String,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        Self {
+            tone_guidelines: Vec::from([
+                "Polite, calm and empathetic, never blaming the customer".to_string(),
+                "Short and clear, without internal jargon".to_string(),
+            ]),
+            policy_constraints: Vec::from([
+                "No promises of refunds, compensation or upgrades".to_string(),
+                "No legal statements or admissions of liability".to_string(),
+                "No personal data of other customers or staff".to_string(),
+            ]),
+            blocked_reply:
+                "Thank you for your message. A member of our team will get back to you shortly."
+                    .to_string(),
+        }
+    }
+}
+
+impl ReviewConfig {
+    // Reads REVIEW_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("REVIEW_CONFIG_PATH", DEFAULT_REVIEW_CONFIG_PATH)
+    }
+
+    // Guidelines as they are given to the reviewer
+    pub fn prompt_text(&self) -> String {
+        format!(
+            "TONE GUIDELINES: {} POLICY CONSTRAINTS: {}",
+            self.tone_guidelines.join("; "),
+            self.policy_constraints.join("; ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_review() {
+        let approved: ResponseReview = ResponseReview::parse(
+            r#"{"verdict": "approve", "rewritten": "ignored", "reason": "Fine"}"#,
+        )
+        .unwrap();
+        assert_eq!(approved.verdict, ReviewVerdict::Approve);
+        assert_eq!(approved.rewritten, None);
+
+        let rewrite: ResponseReview = ResponseReview::parse(
+            r#"```json
+            {"verdict": "rewrite", "issues": ["Promises a free suite"], "rewritten": " We are sorry, a technician is on the way. ", "reason": "Policy"}
+            ```"#,
+        )
+        .unwrap();
+        assert_eq!(rewrite.verdict, ReviewVerdict::Rewrite);
+        assert_eq!(rewrite.issues, ["Promises a free suite"]);
+        assert_eq!(
+            rewrite.rewritten.as_deref(),
+            Some("We are sorry, a technician is on the way.")
+        );
+
+        assert!(ResponseReview::parse(r#"{"verdict": "rewrite", "rewritten": " "}"#).is_err());
+        assert!(ResponseReview::parse(r#"{"verdict": "maybe"}"#).is_err());
+        assert_eq!(
+            ResponseReview::parse(r#"{"verdict": "block", "reason": "Made up opening hours"}"#)
+                .unwrap()
+                .verdict,
+            ReviewVerdict::Block
+        );
+    }
+}
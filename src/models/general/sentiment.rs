@@ -0,0 +1,424 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::helpers::config::load_config;
+use crate::helpers::request::extract_json;
+
+pub const DEFAULT_SENTIMENT_CONFIG_PATH: &str = "config/sentiment.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Polarity {
+    Positive,
+    Neutral,
+    Mixed,
+    Negative,
+}
+
+impl Polarity {
+    pub const ALL: [Polarity; 4] = [
+        Polarity::Positive,
+        Polarity::Neutral,
+        Polarity::Mixed,
+        Polarity::Negative,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Positive => "positive",
+            Self::Neutral => "neutral",
+            Self::Mixed => "mixed",
+            Self::Negative => "negative",
+        }
+    }
+}
+
+impl fmt::Display for Polarity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Accepts any casing and surrounding punctuation, f.e. "Negative."
+impl FromStr for Polarity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|polarity| polarity.as_str() == normalized)
+            .ok_or_else(|| format!("Unknown sentiment polarity '{}'", value))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Emotion {
+    Anger,
+    Frustration,
+    Confusion,
+}
+
+impl Emotion {
+    pub const ALL: [Emotion; 3] = [Emotion::Anger, Emotion::Frustration, Emotion::Confusion];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Anger => "anger",
+            Self::Frustration => "frustration",
+            Self::Confusion => "confusion",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sentiment {
+    pub polarity: Polarity,
+    // How strongly the polarity is expressed, 0 to 1
+    pub intensity: f32,
+    pub emotions: Vec<Emotion>,
+    pub confidence: f32,
+    pub rationale: String,
+}
+
+// Sentiment as answered by the model, before validation
+#[derive(Deserialize)]
+struct RawSentiment {
+    polarity: String,
+    intensity: Option<f32>,
+    #[serde(default)]
+    emotions: Vec<String>,
+    confidence: Option<f32>,
+    #[serde(default)]
+    rationale: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredSentiment {
+    Typed(Sentiment),
+    // Cases stored before sentiment was typed held the bare answer, f.e. "Negative"
+    Legacy(String),
+}
+
+// Reads typed and legacy sentiments; a legacy answer that names no polarity, f.e.
+// "I cannot tell", is read as no sentiment so the case itself still loads
+pub fn deserialize_stored_sentiment<'de, D>(deserializer: D) -> Result<Option<Sentiment>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(
+        match Option::<StoredSentiment>::deserialize(deserializer)? {
+            Some(StoredSentiment::Typed(sentiment)) => Some(sentiment),
+            Some(StoredSentiment::Legacy(answer)) => answer.parse().ok().map(Sentiment::new),
+            None => None,
+        },
+    )
+}
+
+impl Sentiment {
+    // Sentiment known only by its polarity
+    pub fn new(polarity: Polarity) -> Self {
+        Self {
+            polarity,
+            intensity: 1.0,
+            emotions: Vec::from([]),
+            confidence: 1.0,
+            rationale: String::new(),
+        }
+    }
+
+    // Reads the model answer: the requested JSON object, or a bare polarity word as fallback
+    pub fn parse(response: &str) -> Result<Self, String> {
+        if let Ok(raw) = serde_json::from_str::<RawSentiment>(extract_json(response)) {
+            return Ok(Self {
+                polarity: raw.polarity.parse()?,
+                intensity: raw.intensity.unwrap_or(0.5).clamp(0.0, 1.0),
+                emotions: Emotion::ALL
+                    .into_iter()
+                    .filter(|emotion| {
+                        raw.emotions
+                            .iter()
+                            .any(|named| named.trim().eq_ignore_ascii_case(emotion.as_str()))
+                    })
+                    .collect(),
+                confidence: raw.confidence.unwrap_or(0.5).clamp(0.0, 1.0),
+                rationale: raw.rationale.trim().to_string(),
+            });
+        }
+
+        let words: Vec<Polarity> = response
+            .split_whitespace()
+            .filter_map(|word| word.parse().ok())
+            .collect();
+        match words.as_slice() {
+            [polarity, ..] if words.iter().all(|other| other == polarity) => Ok(Self {
+                polarity: *polarity,
+                intensity: 0.5,
+                emotions: Vec::from([]),
+                confidence: 0.5,
+                rationale: String::new(),
+            }),
+            _ => Err(format!("Could not read sentiment from '{}'", response)),
+        }
+    }
+
+    // Signed score from -1 (strongly negative) to 1 (strongly positive), weighted by confidence
+    pub fn valence(&self) -> f32 {
+        let direction: f32 = match self.polarity {
+            Polarity::Positive => 1.0,
+            Polarity::Neutral => 0.0,
+            Polarity::Mixed => -0.5,
+            Polarity::Negative => -1.0,
+        };
+        direction * self.intensity * self.confidence
+    }
+
+    pub fn should_escalate(&self, config: &SentimentConfig) -> bool {
+        let escalating: bool = match self.polarity {
+            Polarity::Negative => true,
+            Polarity::Mixed => config.escalate_mixed,
+            Polarity::Positive | Polarity::Neutral => false,
+        };
+        escalating
+            && self.intensity >= config.escalation_intensity
+            && self.confidence >= config.min_confidence
+    }
+}
+
+impl fmt::Display for Sentiment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2})", self.polarity, self.intensity)?;
+        if !self.emotions.is_empty() {
+            let emotions: Vec<&str> = self
+                .emotions
+                .iter()
+                .map(|emotion| emotion.as_str())
+                .collect();
+            write!(f, " {}", emotions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Sentiment of one customer message; 'message_index' 0 is the initial query and
+// higher indexes are the follow-up messages in order
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SentimentPoint {
+    pub message_index: usize,
+    pub at: DateTime<Local>,
+    pub sentiment: Sentiment,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SentimentTrend {
+    Stable,
+    Deteriorating,
+    Recovering,
+}
+
+impl fmt::Display for SentimentTrend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Stable => "stable",
+            Self::Deteriorating => "deteriorating",
+            Self::Recovering => "recovering",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// What the coordinator should do about escalation given the sentiment so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendDecision {
+    Escalate,
+    CancelEscalation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SentimentConfig {
+    // Negative sentiment at least this intense escalates the case
+    pub escalation_intensity: f32,
+    // Sentiment below this confidence never escalates on its own
+    pub min_confidence: f32,
+    // Whether mixed sentiment escalates like negative sentiment
+    pub escalate_mixed: bool,
+    // How many earlier messages the latest one is compared with
+    pub trend_window: usize,
+    // Change in valence against that window that counts as deteriorating or recovering
+    pub trend_delta: f32,
+    // Whether a recovering customer takes back an escalation made for their sentiment
+    pub cancel_on_recovery: bool,
+}
+
+impl Default for SentimentConfig {
+    fn default() -> Self {
+        Self {
+            escalation_intensity: 0.6,
+            min_confidence: 0.5,
+            escalate_mixed: false,
+            trend_window: 3,
+            trend_delta: 0.4,
+            cancel_on_recovery: true,
+        }
+    }
+}
+
+impl SentimentConfig {
+    // Latest message compared with the average of the messages before it
+    pub fn trend(&self, trajectory: &[SentimentPoint]) -> SentimentTrend {
+        let Some((latest, earlier)) = trajectory.split_last() else {
+            return SentimentTrend::Stable;
+        };
+        let window: &[SentimentPoint] = &earlier[earlier.len().saturating_sub(self.trend_window)..];
+        if window.is_empty() {
+            return SentimentTrend::Stable;
+        }
+        let baseline: f32 = window
+            .iter()
+            .map(|point| point.sentiment.valence())
+            .sum::<f32>()
+            / window.len() as f32;
+        let change: f32 = latest.sentiment.valence() - baseline;
+        if change <= -self.trend_delta {
+            SentimentTrend::Deteriorating
+        } else if change >= self.trend_delta {
+            SentimentTrend::Recovering
+        } else {
+            SentimentTrend::Stable
+        }
+    }
+
+    // Escalate when the latest message crosses the thresholds or the customer is getting
+    // angrier; take the escalation back when they recover below the thresholds
+    pub fn decide(&self, trajectory: &[SentimentPoint]) -> Option<TrendDecision> {
+        let latest: &Sentiment = &trajectory.last()?.sentiment;
+        if latest.should_escalate(self) {
+            return Some(TrendDecision::Escalate);
+        }
+        match self.trend(trajectory) {
+            SentimentTrend::Deteriorating if latest.valence() < 0.0 => {
+                Some(TrendDecision::Escalate)
+            }
+            SentimentTrend::Recovering if self.cancel_on_recovery => {
+                Some(TrendDecision::CancelEscalation)
+            }
+            _ => None,
+        }
+    }
+
+    // Reads SENTIMENT_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("SENTIMENT_CONFIG_PATH", DEFAULT_SENTIMENT_CONFIG_PATH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_sentiment() {
+        let sentiment: Sentiment = Sentiment::parse(
+            r#"```json
+            {"polarity": "Negative", "intensity": 0.9, "emotions": ["Anger", "boredom", "frustration"],
+             "confidence": 0.8, "rationale": "Guest demands an immediate fix."}
+            ```"#,
+        )
+        .unwrap();
+        assert_eq!(sentiment.polarity, Polarity::Negative);
+        assert_eq!(sentiment.emotions, [Emotion::Anger, Emotion::Frustration]);
+        assert!(sentiment.should_escalate(&SentimentConfig::default()));
+
+        let bare: Sentiment = Sentiment::parse("Negative.").unwrap();
+        assert_eq!(bare.polarity, Polarity::Negative);
+        assert!(!bare.should_escalate(&SentimentConfig::default()));
+
+        assert_eq!(
+            Sentiment::parse("Neutral").unwrap().polarity,
+            Polarity::Neutral
+        );
+        assert!(Sentiment::parse("Positive or Negative").is_err());
+        assert!(Sentiment::parse("I cannot tell").is_err());
+
+        let stored: Sentiment =
+            serde_json::from_str(&serde_json::to_string(&sentiment).unwrap()).unwrap();
+        assert_eq!(stored, sentiment);
+    }
+
+    #[derive(Deserialize)]
+    struct Stored {
+        #[serde(default, deserialize_with = "deserialize_stored_sentiment")]
+        sentiment: Option<Sentiment>,
+    }
+
+    #[test]
+    fn tests_deserialize_legacy_sentiment() {
+        let read = |json: &str| serde_json::from_str::<Stored>(json).unwrap().sentiment;
+        assert_eq!(
+            read(r#"{ "sentiment": "Negative" }"#),
+            Some(Sentiment::new(Polarity::Negative))
+        );
+        // Answers that name no polarity do not keep the case from loading
+        assert_eq!(read(r#"{ "sentiment": "I cannot tell" }"#), None);
+        assert_eq!(read(r#"{ "sentiment": null }"#), None);
+        assert_eq!(read("{}"), None);
+    }
+
+    fn trajectory(answers: &[&str]) -> Vec<SentimentPoint> {
+        answers
+            .iter()
+            .enumerate()
+            .map(|(message_index, answer)| SentimentPoint {
+                message_index,
+                at: Local::now(),
+                sentiment: Sentiment::parse(answer).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tests_sentiment_trend() {
+        let config: SentimentConfig = SentimentConfig::default();
+        assert_eq!(config.decide(&[]), None);
+
+        let calm: Vec<SentimentPoint> = trajectory(&[
+            r#"{"polarity": "neutral", "intensity": 0.2, "confidence": 0.9}"#,
+            r#"{"polarity": "neutral", "intensity": 0.3, "confidence": 0.9}"#,
+        ]);
+        assert_eq!(config.trend(&calm), SentimentTrend::Stable);
+        assert_eq!(config.decide(&calm), None);
+
+        // Still below the escalation intensity, but clearly getting worse
+        let souring: Vec<SentimentPoint> = trajectory(&[
+            r#"{"polarity": "positive", "intensity": 0.4, "confidence": 0.9}"#,
+            r#"{"polarity": "negative", "intensity": 0.4, "confidence": 0.9}"#,
+        ]);
+        assert_eq!(config.trend(&souring), SentimentTrend::Deteriorating);
+        assert_eq!(config.decide(&souring), Some(TrendDecision::Escalate));
+
+        let recovering: Vec<SentimentPoint> = trajectory(&[
+            r#"{"polarity": "negative", "intensity": 0.9, "confidence": 0.9}"#,
+            r#"{"polarity": "positive", "intensity": 0.5, "confidence": 0.8}"#,
+        ]);
+        assert_eq!(config.trend(&recovering), SentimentTrend::Recovering);
+        assert_eq!(
+            config.decide(&recovering),
+            Some(TrendDecision::CancelEscalation)
+        );
+        assert_eq!(
+            SentimentConfig {
+                cancel_on_recovery: false,
+                ..config
+            }
+            .decide(&recovering),
+            None
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::helpers::config::load_config;
+
+use super::support_case::SupportCase;
+
+pub const DEFAULT_SIMILARITY_CONFIG_PATH: &str = "config/similarity.json";
+
+// Words that say nothing about the issue itself
+const STOPWORDS: [&str; 48] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "can", "could", "do", "for", "from",
+    "have", "hello", "hi", "i", "im", "in", "is", "it", "its", "me", "my", "no", "not", "of",
+    "on", "or", "our", "please", "so", "that", "the", "there", "this", "to", "us", "was", "we",
+    "were", "what", "with", "would", "you", "your", "yours",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SimilarityConfig {
+    // Scores at or above this mark a duplicate
+    pub duplicate_threshold: f32,
+    // Scores at or above this mark a related case
+    pub related_threshold: f32,
+    // Added to the score when both cases belong to the same customer
+    pub same_customer_boost: f32,
+    // Only cases created within this many days are compared
+    pub window_days: i64,
+    pub max_links: usize,
+    // Blend query embeddings into the score; needs AZURE_OPEN_AI_EMBEDDING_DEPLOYMENT_NAME
+    pub use_embeddings: bool,
+    // Share of the score taken from embeddings when both cases have one
+    pub embedding_weight: f32,
+    // Answer duplicates of resolved cases with the earlier resolution
+    pub reuse_resolutions: bool,
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_threshold: 0.8,
+            related_threshold: 0.5,
+            same_customer_boost: 0.15,
+            window_days: 14,
+            max_links: 5,
+            use_embeddings: false,
+            embedding_weight: 0.5,
+            reuse_resolutions: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseRelation {
+    Duplicate,
+    Related,
+}
+
+impl fmt::Display for CaseRelation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::Duplicate => "duplicate",
+            Self::Related => "related",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Link from one case to a similar one, stored on both cases
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaseLink {
+    pub case_id: Uuid,
+    pub relation: CaseRelation,
+    pub score: f32,
+    pub linked_at: DateTime<Local>,
+}
+
+// Lowercased words without punctuation, stopwords and plural 's'
+pub fn normalize_text(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() > 1 && !STOPWORDS.contains(word))
+        .map(|word| {
+            if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+                word[..word.len() - 1].to_string()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect()
+}
+
+// Dice coefficient of the normalized words of both texts
+pub fn text_similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize_text(a), normalize_text(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(&b).count() as f32 / (a.len() + b.len()) as f32
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms: f32 = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+impl SimilarityConfig {
+    // Reads SIMILARITY_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("SIMILARITY_CONFIG_PATH", DEFAULT_SIMILARITY_CONFIG_PATH)
+    }
+
+    pub fn score(&self, support_case: &SupportCase, other: &SupportCase) -> f32 {
+        let text: f32 = text_similarity(&support_case.customer_query, &other.customer_query);
+        let mut score: f32 = match (&support_case.embedding, &other.embedding) {
+            (Some(a), Some(b)) => {
+                self.embedding_weight * cosine_similarity(a, b)
+                    + (1.0 - self.embedding_weight) * text
+            }
+            _ => text,
+        };
+        if support_case.customer_id.is_some() && support_case.customer_id == other.customer_id {
+            score += self.same_customer_boost;
+        }
+        score.min(1.0)
+    }
+
+    // Likely duplicates and related cases among 'candidates', best match first. Candidates
+    // must share the customer or the support context and fall within the time window.
+    pub fn find_similar(&self, support_case: &SupportCase, candidates: &[SupportCase]) -> Vec<CaseLink> {
+        let since: DateTime<Local> = support_case.created_at - Duration::days(self.window_days);
+        let mut links: Vec<CaseLink> = candidates
+            .iter()
+            .filter(|other| {
+                other.case_id != support_case.case_id
+                    && other.created_at >= since
+                    && ((support_case.customer_id.is_some()
+                        && other.customer_id == support_case.customer_id)
+                        || other
+                            .support_context
+                            .eq_ignore_ascii_case(&support_case.support_context))
+            })
+            .filter_map(|other| {
+                let score: f32 = self.score(support_case, other);
+                let relation: CaseRelation = if score >= self.duplicate_threshold {
+                    CaseRelation::Duplicate
+                } else if score >= self.related_threshold {
+                    CaseRelation::Related
+                } else {
+                    return None;
+                };
+                Some(CaseLink {
+                    case_id: other.case_id,
+                    relation,
+                    score,
+                    linked_at: Local::now(),
+                })
+            })
+            .collect();
+        links.sort_by(|a, b| b.score.total_cmp(&a.score));
+        links.truncate(self.max_links);
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_find_similar_cases() {
+        let config: SimilarityConfig = SimilarityConfig::default();
+        let customer_id: Uuid = Uuid::new_v4();
+        let case = |context: &str, query: &str| {
+            let mut support_case: SupportCase = SupportCase::new(context.to_string(), query.to_string());
+            support_case.customer_id = Some(customer_id);
+            support_case
+        };
+
+        let new_case: SupportCase = case("5 Star Hotel", "The air conditioning in room 12 is broken!");
+        let same_issue: SupportCase = case("5 Star Hotel", "Air conditioning broken in my room 12.");
+        let related: SupportCase = case("5 Star Hotel", "The heating in room 12 is making noise.");
+        let other_topic: SupportCase = case("5 Star Hotel", "Can I book a table for dinner?");
+        let mut other_customer: SupportCase = case("Car Rental", "Air conditioning is broken");
+        other_customer.customer_id = None;
+
+        let links: Vec<CaseLink> = config.find_similar(
+            &new_case,
+            &[other_topic, related.clone(), other_customer, same_issue.clone(), new_case.clone()],
+        );
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].case_id, same_issue.case_id);
+        assert_eq!(links[0].relation, CaseRelation::Duplicate);
+        assert_eq!(links[1].case_id, related.case_id);
+        assert_eq!(links[1].relation, CaseRelation::Related);
+
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < f32::EPSILON);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::helpers::config::load_config;
+
+use super::priority::Priority;
+
+pub const DEFAULT_SLA_CONFIG_PATH: &str = "config/sla.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SlaPolicy {
+    pub first_response_minutes: i64,
+    pub resolution_minutes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SlaConfig {
+    // Share of the allowed time after which a target counts as at risk
+    pub warning_ratio: f32,
+    pub p1: SlaPolicy,
+    pub p2: SlaPolicy,
+    pub p3: SlaPolicy,
+    pub p4: SlaPolicy,
+}
+
+impl Default for SlaConfig {
+    fn default() -> Self {
+        Self {
+            warning_ratio: 0.8,
+            p1: SlaPolicy {
+                first_response_minutes: 15,
+                resolution_minutes: 4 * 60,
+            },
+            p2: SlaPolicy {
+                first_response_minutes: 60,
+                resolution_minutes: 24 * 60,
+            },
+            p3: SlaPolicy {
+                first_response_minutes: 4 * 60,
+                resolution_minutes: 3 * 24 * 60,
+            },
+            p4: SlaPolicy {
+                first_response_minutes: 24 * 60,
+                resolution_minutes: 7 * 24 * 60,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaState {
+    OnTrack,
+    AtRisk,
+    Breached,
+    Met,
+}
+
+impl fmt::Display for SlaState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label: &str = match self {
+            Self::OnTrack => "on track",
+            Self::AtRisk => "at risk",
+            Self::Breached => "breached",
+            Self::Met => "met",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// SLA bookkeeping stored on a case. Breach flags stay set once raised.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SlaTracking {
+    pub first_response_due: Option<DateTime<Local>>,
+    pub resolution_due: Option<DateTime<Local>>,
+    pub first_response_breached: bool,
+    pub resolution_breached: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlaEvaluation {
+    pub first_response: SlaState,
+    pub resolution: SlaState,
+}
+
+impl SlaEvaluation {
+    pub fn needs_attention(&self) -> bool {
+        [self.first_response, self.resolution]
+            .iter()
+            .any(|state| matches!(state, SlaState::AtRisk | SlaState::Breached))
+    }
+}
+
+impl SlaConfig {
+    // Reads SLA_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("SLA_CONFIG_PATH", DEFAULT_SLA_CONFIG_PATH)
+    }
+
+    pub fn policy(&self, priority: Priority) -> &SlaPolicy {
+        match priority {
+            Priority::P1 => &self.p1,
+            Priority::P2 => &self.p2,
+            Priority::P3 => &self.p3,
+            Priority::P4 => &self.p4,
+        }
+    }
+
+    // State of a single target that started at 'start' and is due at 'due'
+    pub fn target_state(
+        &self,
+        start: DateTime<Local>,
+        due: DateTime<Local>,
+        completed_at: Option<DateTime<Local>>,
+        now: DateTime<Local>,
+    ) -> SlaState {
+        if let Some(completed_at) = completed_at {
+            return if completed_at <= due {
+                SlaState::Met
+            } else {
+                SlaState::Breached
+            };
+        }
+        if now > due {
+            return SlaState::Breached;
+        }
+        let allowed: i64 = (due - start).num_seconds().max(1);
+        let elapsed: i64 = (now - start).num_seconds();
+        if elapsed as f32 >= allowed as f32 * self.warning_ratio {
+            SlaState::AtRisk
+        } else {
+            SlaState::OnTrack
+        }
+    }
+
+    pub fn due_times(
+        &self,
+        priority: Priority,
+        created_at: DateTime<Local>,
+    ) -> (DateTime<Local>, DateTime<Local>) {
+        let policy: &SlaPolicy = self.policy(priority);
+        (
+            created_at + Duration::minutes(policy.first_response_minutes),
+            created_at + Duration::minutes(policy.resolution_minutes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_sla_target_state() {
+        let config: SlaConfig = SlaConfig::default();
+        let start: DateTime<Local> = Local::now();
+        let (first_response_due, _) = config.due_times(Priority::P1, start);
+        assert_eq!(first_response_due - start, Duration::minutes(15));
+
+        let minutes = |m: i64| start + Duration::minutes(m);
+        assert_eq!(
+            config.target_state(start, first_response_due, None, minutes(5)),
+            SlaState::OnTrack
+        );
+        assert_eq!(
+            config.target_state(start, first_response_due, None, minutes(13)),
+            SlaState::AtRisk
+        );
+        assert_eq!(
+            config.target_state(start, first_response_due, None, minutes(16)),
+            SlaState::Breached
+        );
+        assert_eq!(
+            config.target_state(start, first_response_due, Some(minutes(10)), minutes(60)),
+            SlaState::Met
+        );
+        assert_eq!(
+            config.target_state(start, first_response_due, Some(minutes(20)), minutes(60)),
+            SlaState::Breached
+        );
+    }
+}
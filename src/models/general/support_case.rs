@@ -1,21 +1,156 @@
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 use uuid::Uuid;
 
-use crate::models::ai::chatgpt::Message;
+use super::action::ActionResult;
+use super::attachment::Attachment;
+use super::case_status::{CaseStatus, StatusTransition};
+use super::escalation::{EscalationChoice, EscalationDecision};
+use super::customer::{Customer, CustomerContext, CustomerTier};
+use super::faq::FaqHit;
+use super::handoff::Handoff;
+use super::intent::Intent;
+use super::language::CaseLanguage;
+use super::llm_call::{deserialize_trace, LlmCall};
+use super::memory::AgentMemory;
+use super::notification::NotificationDelivery;
+use super::policy::PolicyViolation;
+use super::priority::{Priority, PriorityConfig};
+use super::sentiment::{deserialize_stored_sentiment, Sentiment, SentimentPoint};
+use super::similarity::CaseLink;
+use super::sla::{SlaConfig, SlaEvaluation, SlaState, SlaTracking};
+use super::taxonomy::CaseCategory;
+use super::ticket::TicketReference;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomerMessage {
+    pub at: DateTime<Local>,
+    pub text: String,
+}
+
+// Prompt context of a case; messages, actions and attachments are oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromptContext {
+    // Support context, customer and language, always sent
+    pub header: String,
+    pub messages: Vec<String>,
+    pub last_reply: Option<String>,
+    pub intent: Option<String>,
+    pub actions: Vec<String>,
+    pub attachments: Vec<String>,
+    // Details an agent adds for its own prompt, always sent
+    pub notes: Vec<String>,
+}
+
+impl PromptContext {
+    pub fn text(&self) -> String {
+        let mut context: String = self.header.clone();
+        if !self.messages.is_empty() {
+            context.push_str(&format!(
+                " EARLIER CUSTOMER MESSAGES: {}",
+                self.messages.join(" | ")
+            ));
+        }
+        if let Some(reply) = &self.last_reply {
+            context.push_str(&format!(" LAST REPLY: {}", reply));
+        }
+        if let Some(intent) = &self.intent {
+            context.push_str(&format!(" INTENT: {}", intent));
+        }
+        if !self.actions.is_empty() {
+            context.push_str(&format!(" ACTIONS TAKEN: {}", self.actions.join(", ")));
+        }
+        if !self.attachments.is_empty() {
+            context.push_str(&format!(" ATTACHMENTS: {}", self.attachments.join(" ")));
+        }
+        for note in &self.notes {
+            context.push_str(&format!(" {}", note));
+        }
+        context
+    }
+
+    // Sections the budget may shorten, in the order they are shortened
+    pub fn shortenable_mut(&mut self) -> [&mut Vec<String>; 3] {
+        [&mut self.messages, &mut self.attachments, &mut self.actions]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupportCase {
     pub case_id: Uuid,
     pub support_context: String,
+    #[serde(default)]
+    pub customer_id: Option<Uuid>,
+    #[serde(default)]
+    pub customer: Option<CustomerContext>,
     pub customer_query: String,
+    // Messages the customer sent on the case after 'customer_query'
+    #[serde(default)]
+    pub customer_messages: Vec<CustomerMessage>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
     pub support_response: Option<String>,
-    pub sentiment: Option<String>,
-    pub should_escalate: bool,
-    pub escalated: bool,
-    pub needs_upper_management_attention: bool,
+    // Customer messages answered from the FAQ without asking the model
+    #[serde(default)]
+    pub faq_hits: Vec<FaqHit>,
+    // English reply the agents wrote when 'support_response' holds its translation
+    #[serde(default)]
+    pub original_response: Option<String>,
+    #[serde(default)]
+    pub language: Option<CaseLanguage>,
+    // Sentiment of the latest customer message
+    #[serde(deserialize_with = "deserialize_stored_sentiment")]
+    pub sentiment: Option<Sentiment>,
+    #[serde(default)]
+    pub sentiment_trajectory: Vec<SentimentPoint>,
+    #[serde(default)]
+    pub categories: Vec<CaseCategory>,
+    // Intent of the latest classified customer message
+    #[serde(default)]
+    pub intent: Option<Intent>,
+    #[serde(default)]
+    pub status: CaseStatus,
+    #[serde(default)]
+    pub status_history: Vec<StatusTransition>,
+    #[serde(default)]
+    pub escalation_decision: Option<EscalationDecision>,
+    #[serde(default)]
+    pub action_results: Vec<ActionResult>,
+    // Replies and actions that broke the support context's policy
+    #[serde(default)]
+    pub policy_violations: Vec<PolicyViolation>,
+    #[serde(default)]
+    pub tickets: Vec<TicketReference>,
+    // Summary for the people taking over, written when the case is escalated
+    #[serde(default)]
+    pub handoff: Option<Handoff>,
+    // Upper management notifications sent for the case, one entry per channel
+    #[serde(default)]
+    pub notifications: Vec<NotificationDelivery>,
+    #[serde(default)]
+    pub customer_tier: CustomerTier,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub priority_score: f32,
+    #[serde(default)]
+    pub first_response_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub sla: SlaTracking,
+    #[serde(default)]
+    pub related_cases: Vec<CaseLink>,
+    #[serde(default)]
+    pub duplicate_of: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
-    pub trace: Vec<Message>,
+    #[serde(deserialize_with = "deserialize_trace")]
+    pub trace: Vec<LlmCall>,
+    // Working memory of each agent, by role
+    #[serde(default)]
+    pub agent_memory: Vec<AgentMemory>,
     pub supported_actions: Vec<String>
 }
 
@@ -24,19 +159,294 @@ impl SupportCase {
         Self {
             case_id: Uuid::new_v4(),
             support_context: context,
+            customer_id: None,
+            customer: None,
             customer_query: query,
+            customer_messages: Vec::from([]),
+            attachments: Vec::from([]),
             support_response: None,
+            faq_hits: Vec::from([]),
+            original_response: None,
+            language: None,
             sentiment: None,
-            should_escalate: false,
-            escalated: false,
-            needs_upper_management_attention: false,
+            sentiment_trajectory: Vec::from([]),
+            categories: Vec::from([]),
+            intent: None,
+            status: CaseStatus::New,
+            status_history: Vec::from([]),
+            escalation_decision: None,
+            action_results: Vec::from([]),
+            policy_violations: Vec::from([]),
+            tickets: Vec::from([]),
+            handoff: None,
+            notifications: Vec::from([]),
+            customer_tier: CustomerTier::Standard,
+            priority: Priority::P4,
+            priority_score: 0.0,
+            first_response_at: None,
+            sla: SlaTracking::default(),
+            related_cases: Vec::from([]),
+            duplicate_of: None,
+            embedding: None,
             created_at: Local::now(),
             updated_at: Local::now(),
             trace: Vec::from([]),
+            agent_memory: Vec::from([]),
             supported_actions: Vec::from([])
         }
     }
     pub fn updated(&mut self) {
         self.updated_at = Local::now()
     }
+    // Link the case to a customer and snapshot their profile and recent history
+    pub fn attach_customer(&mut self, customer: &Customer, recent_cases: &[SupportCase]) {
+        self.customer_id = Some(customer.customer_id);
+        self.customer_tier = customer.tier;
+        self.customer = Some(CustomerContext::new(customer, recent_cases));
+        self.updated();
+    }
+    // What agents answer: the latest follow-up, or the initial query
+    pub fn latest_customer_message(&self) -> &str {
+        self.customer_messages
+            .last()
+            .map(|message| message.text.as_str())
+            .unwrap_or(&self.customer_query)
+    }
+    // Index of the latest customer message, 0 being the initial query
+    pub fn latest_message_index(&self) -> usize {
+        self.customer_messages.len()
+    }
+    pub fn add_customer_message(&mut self, text: String) {
+        self.customer_messages.push(CustomerMessage {
+            at: Local::now(),
+            text,
+        });
+        self.updated();
+    }
+    pub fn memory_for(&self, role: &str) -> Option<&AgentMemory> {
+        self.agent_memory.iter().find(|memory| memory.role == role)
+    }
+    pub fn memory_mut(&mut self, role: &str) -> &mut AgentMemory {
+        let index: usize = match self
+            .agent_memory
+            .iter()
+            .position(|memory| memory.role == role)
+        {
+            Some(index) => index,
+            None => {
+                self.agent_memory.push(AgentMemory::new(role));
+                self.agent_memory.len() - 1
+            }
+        };
+        &mut self.agent_memory[index]
+    }
+    // Whether the latest customer message was answered from the FAQ
+    pub fn answered_from_faq(&self) -> bool {
+        self.faq_hits
+            .last()
+            .is_some_and(|hit| hit.message_index == self.latest_message_index())
+    }
+    // Intent of the latest customer message, if it was classified
+    pub fn current_intent(&self) -> Option<&Intent> {
+        self.intent
+            .as_ref()
+            .filter(|intent| intent.message_index == self.latest_message_index())
+    }
+    // Support context plus what we know about the customer, for agent prompts; in sections,
+    // so a request over budget can shorten the long ones
+    pub fn prompt_context(&self) -> PromptContext {
+        let mut header: String = match &self.customer {
+            Some(customer) => format!("{} CUSTOMER: {}", self.support_context, customer.prompt_text()),
+            None => self.support_context.clone(),
+        };
+        if let Some(language) = self.language.as_ref().filter(|language| !language.is_english()) {
+            header.push_str(&format!(" CUSTOMER LANGUAGE: {}", language.name));
+        }
+        let mut context: PromptContext = PromptContext {
+            header,
+            actions: self
+                .action_results
+                .iter()
+                .map(|result| format!("{} ({})", result.action, result.status))
+                .collect(),
+            attachments: self
+                .attachments
+                .iter()
+                .map(|attachment| attachment.prompt_text())
+                .collect(),
+            intent: self.current_intent().map(|intent| intent.prompt_text()),
+            ..Default::default()
+        };
+        // On follow-ups agents answer the latest message and see how the conversation went
+        if let Some((_, earlier)) = self.customer_messages.split_last() {
+            context.messages = Vec::from([self.customer_query.clone()]);
+            context
+                .messages
+                .extend(earlier.iter().map(|message| message.text.clone()));
+            context.last_reply = self
+                .original_response
+                .clone()
+                .or(self.support_response.clone());
+        }
+        context
+    }
+    // Move the case to a new status, enforcing the transition table
+    pub fn transition(
+        &mut self,
+        to: CaseStatus,
+        requested_by: &str,
+        reason: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.status.can_transition_to(to) {
+            return Err(format!(
+                "Case {} cannot move from '{}' to '{}'",
+                self.case_id, self.status, to
+            )
+            .into());
+        }
+        let at: DateTime<Local> = Local::now();
+        self.status_history.push(StatusTransition {
+            from: self.status,
+            to,
+            at,
+            requested_by: requested_by.to_string(),
+            reason: reason.to_string(),
+        });
+        self.status = to;
+        self.updated_at = at;
+        Ok(())
+    }
+    // Whether the escalated case waits for upper management instead of a resolving action
+    pub fn needs_upper_management(&self) -> bool {
+        self.status == CaseStatus::Escalated
+            && self
+                .escalation_decision
+                .as_ref()
+                .is_some_and(|decision| decision.choice == EscalationChoice::UpperManagement)
+    }
+    // Recompute priority from the case's current sentiment, wording, tier and age
+    pub fn reprioritize(&mut self, config: &PriorityConfig) -> Priority {
+        self.priority_score = config.score(self, Local::now());
+        self.priority = config.priority_for_score(self.priority_score);
+        self.priority
+    }
+    // When the case was last resolved or closed, if it currently is
+    pub fn resolved_at(&self) -> Option<DateTime<Local>> {
+        if !matches!(self.status, CaseStatus::Resolved | CaseStatus::Closed) {
+            return None;
+        }
+        self.status_history
+            .iter()
+            .rev()
+            .find(|transition| matches!(transition.to, CaseStatus::Resolved | CaseStatus::Closed))
+            .map(|transition| transition.at)
+    }
+    // Update due times for the current priority and record any breach. Completed targets keep the
+    // due time they were completed against, so a later rise in priority does not breach them.
+    pub fn refresh_sla(&mut self, config: &SlaConfig, now: DateTime<Local>) -> SlaEvaluation {
+        let (mut first_response_due, mut resolution_due) =
+            config.due_times(self.priority, self.created_at);
+        if let (Some(_), Some(due)) = (self.first_response_at, self.sla.first_response_due) {
+            first_response_due = due;
+        }
+        if let (Some(_), Some(due)) = (self.resolved_at(), self.sla.resolution_due) {
+            resolution_due = due;
+        }
+        let evaluation: SlaEvaluation = SlaEvaluation {
+            first_response: config.target_state(
+                self.created_at,
+                first_response_due,
+                self.first_response_at,
+                now,
+            ),
+            resolution: config.target_state(
+                self.created_at,
+                resolution_due,
+                self.resolved_at(),
+                now,
+            ),
+        };
+        self.sla.first_response_due = Some(first_response_due);
+        self.sla.resolution_due = Some(resolution_due);
+        self.sla.first_response_breached |= evaluation.first_response == SlaState::Breached;
+        self.sla.resolution_breached |= evaluation.resolution == SlaState::Breached;
+        evaluation
+    }
+    // Add or refresh the link to a similar case
+    pub fn link_case(&mut self, link: CaseLink) {
+        self.related_cases.retain(|existing| existing.case_id != link.case_id);
+        self.related_cases.push(link);
+        self.updated();
+    }
+    // Whether the case has been escalated at any point of its lifecycle
+    pub fn is_escalated(&self) -> bool {
+        self.status == CaseStatus::Escalated
+            || self
+                .status_history
+                .iter()
+                .any(|transition| transition.to == CaseStatus::Escalated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_support_case_transition() {
+        let mut support_case: SupportCase =
+            SupportCase::new("5 Star Hotel".to_string(), "My room is cold.".to_string());
+
+        support_case
+            .transition(CaseStatus::InProgress, "Coordinator", "Handling request")
+            .unwrap();
+        support_case
+            .transition(CaseStatus::Escalated, "Psychologist", "Negative sentiment")
+            .unwrap();
+        assert!(support_case
+            .transition(CaseStatus::Reopened, "Coordinator", "Invalid")
+            .is_err());
+
+        assert_eq!(support_case.status, CaseStatus::Escalated);
+        assert_eq!(support_case.status_history.len(), 2);
+        assert_eq!(support_case.status_history[1].from, CaseStatus::InProgress);
+        assert_eq!(support_case.status_history[1].requested_by, "Psychologist");
+        assert!(support_case.is_escalated());
+    }
+
+    #[test]
+    fn tests_support_case_sla_after_priority_rise() {
+        let config: SlaConfig = SlaConfig::default();
+        let mut support_case: SupportCase =
+            SupportCase::new("5 Star Hotel".to_string(), "My room is cold.".to_string());
+        let start: DateTime<Local> = support_case.created_at;
+        support_case.priority = Priority::P3;
+        support_case.refresh_sla(&config, start);
+
+        // Answered within the P3 target, which is missed by the P1 target
+        support_case.first_response_at = Some(start + chrono::Duration::minutes(30));
+        let evaluation: SlaEvaluation =
+            support_case.refresh_sla(&config, start + chrono::Duration::minutes(30));
+        assert_eq!(evaluation.first_response, SlaState::Met);
+
+        support_case.priority = Priority::P1;
+        let evaluation: SlaEvaluation =
+            support_case.refresh_sla(&config, start + chrono::Duration::hours(2));
+        assert_eq!(evaluation.first_response, SlaState::Met);
+        assert!(!support_case.sla.first_response_breached);
+        assert_eq!(
+            support_case.sla.first_response_due,
+            Some(start + chrono::Duration::hours(4))
+        );
+        // Open targets follow the new priority
+        assert_eq!(evaluation.resolution, SlaState::OnTrack);
+        assert_eq!(
+            support_case.sla.resolution_due,
+            Some(start + chrono::Duration::hours(4))
+        );
+        let evaluation: SlaEvaluation =
+            support_case.refresh_sla(&config, start + chrono::Duration::hours(5));
+        assert_eq!(evaluation.resolution, SlaState::Breached);
+        assert!(support_case.sla.resolution_breached);
+    }
 }
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_TAXONOMY_PATH: &str = "config/taxonomy.json";
+
+// What the coordinator does with a case in this category
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CategoryRoute {
+    #[default]
+    Answer,
+    Escalate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaxonomyCategory {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub subcategories: Vec<String>,
+    #[serde(default)]
+    pub route: CategoryRoute,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Taxonomy {
+    pub categories: Vec<TaxonomyCategory>,
+    // Categories below this confidence are kept on the case but never used for routing
+    pub min_routing_confidence: f32,
+}
+
+// Category assigned to a case by the categorization agent
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CaseCategory {
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub confidence: f32,
+}
+
+impl Default for Taxonomy {
+    fn default() -> Self {
+        let category = |name: &str, description: &str, subcategories: &[&str], route: CategoryRoute| {
+            TaxonomyCategory {
+                name: name.to_string(),
+                description: description.to_string(),
+                subcategories: subcategories.iter().map(|s| s.to_string()).collect(),
+                route,
+            }
+        };
+        Self {
+            categories: vec![
+                category(
+                    "billing",
+                    "Charges, invoices, refunds and payments",
+                    &["refund", "invoice", "overcharge", "payment"],
+                    CategoryRoute::Answer,
+                ),
+                category(
+                    "technical",
+                    "Something does not work as expected",
+                    &["equipment", "connectivity", "access"],
+                    CategoryRoute::Answer,
+                ),
+                category(
+                    "booking",
+                    "Reservations, changes and cancellations",
+                    &["new", "change", "cancellation"],
+                    CategoryRoute::Answer,
+                ),
+                category(
+                    "complaint",
+                    "Dissatisfaction with service, staff or quality",
+                    &["service", "staff", "quality", "cleanliness"],
+                    CategoryRoute::Escalate,
+                ),
+                category(
+                    "safety",
+                    "Health, safety, security or legal concerns",
+                    &["injury", "security", "legal"],
+                    CategoryRoute::Escalate,
+                ),
+                category(
+                    "general",
+                    "Questions and requests that fit nowhere else",
+                    &["information", "feedback"],
+                    CategoryRoute::Answer,
+                ),
+            ],
+            min_routing_confidence: 0.6,
+        }
+    }
+}
+
+impl Taxonomy {
+    // Reads TAXONOMY_PATH, falling back to the built-in taxonomy
+    pub fn load() -> Self {
+        load_config("TAXONOMY_PATH", DEFAULT_TAXONOMY_PATH)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&TaxonomyCategory> {
+        self.categories
+            .iter()
+            .find(|category| category.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    // Taxonomy as shown to the model
+    pub fn prompt_text(&self) -> String {
+        self.categories
+            .iter()
+            .map(|category| {
+                format!(
+                    "- {}: {} (subcategories: {})",
+                    category.name,
+                    category.description,
+                    category.subcategories.join(", ")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Drops categories that are not in the taxonomy and normalizes names and confidence
+    pub fn validate(&self, categories: Vec<CaseCategory>) -> Vec<CaseCategory> {
+        categories
+            .into_iter()
+            .filter_map(|assigned| {
+                let category: &TaxonomyCategory = self.find(&assigned.category)?;
+                let subcategory: Option<String> = assigned.subcategory.and_then(|subcategory| {
+                    category
+                        .subcategories
+                        .iter()
+                        .find(|known| known.eq_ignore_ascii_case(subcategory.trim()))
+                        .cloned()
+                });
+                Some(CaseCategory {
+                    category: category.name.clone(),
+                    subcategory,
+                    confidence: assigned.confidence.clamp(0.0, 1.0),
+                })
+            })
+            .collect()
+    }
+
+    // First confidently assigned category that asks for escalation
+    pub fn escalating_category<'a>(&self, categories: &'a [CaseCategory]) -> Option<&'a CaseCategory> {
+        categories.iter().find(|assigned| {
+            assigned.confidence >= self.min_routing_confidence
+                && self
+                    .find(&assigned.category)
+                    .map(|category| category.route == CategoryRoute::Escalate)
+                    .unwrap_or(false)
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::escalation::EscalationChoice;
+use super::priority::Priority;
+use super::support_case::SupportCase;
+use crate::helpers::config::load_config;
+
+pub const DEFAULT_TICKET_CONFIG_PATH: &str = "config/tickets.json";
+
+// Characters of the customer message used in ticket titles
+const TITLE_QUERY_CHARS: usize = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptRole {
+    Customer,
+    Support,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptEntry {
+    pub role: TranscriptRole,
+    pub at: DateTime<Local>,
+    pub text: String,
+}
+
+// Ticket as handed to a helpdesk backend
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Ticket {
+    pub case_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub priority: Priority,
+    pub action: Option<String>,
+    pub labels: Vec<String>,
+    pub transcript: Vec<TranscriptEntry>,
+}
+
+impl Ticket {
+    pub fn from_case(support_case: &SupportCase) -> Self {
+        let action: Option<String> = support_case
+            .escalation_decision
+            .as_ref()
+            .map(|decision| decision.choice.to_string());
+        let subject: String = support_case
+            .customer_query
+            .chars()
+            .take(TITLE_QUERY_CHARS)
+            .collect();
+        let title: String = format!(
+            "[{}] {}: {}",
+            support_case.priority,
+            action.as_deref().unwrap_or("Escalation"),
+            subject
+        );
+
+        let mut description: Vec<String> = Vec::from([
+            format!("Case: {}", support_case.case_id),
+            format!("Context: {}", support_case.support_context),
+        ]);
+        if let Some(customer) = &support_case.customer {
+            description.push(format!("Customer: {} ({})", customer.name, customer.tier));
+        }
+        if let Some(sentiment) = &support_case.sentiment {
+            description.push(format!("Sentiment: {}", sentiment));
+        }
+        if let Some(decision) = &support_case.escalation_decision {
+            let label: &str = match decision.choice {
+                EscalationChoice::UpperManagement => "Handed to upper management",
+                EscalationChoice::Action { .. } => "Chosen action",
+            };
+            description.push(format!(
+                "{}: {} ({})",
+                label, decision.choice, decision.reason
+            ));
+        }
+        for result in &support_case.action_results {
+            description.push(format!(
+                "Action run: {} via {} {}: {}",
+                result.action, result.handler, result.status, result.output
+            ));
+        }
+
+        if let Some(handoff) = &support_case.handoff {
+            description.push(format!("\nHandoff:\n{}", handoff));
+        }
+
+        let mut labels: Vec<String> = support_case
+            .categories
+            .iter()
+            .map(|category| category.category.clone())
+            .collect();
+        if support_case.needs_upper_management() {
+            labels.push("upper-management".to_string());
+        }
+
+        Self {
+            case_id: support_case.case_id,
+            title,
+            description: description.join("\n"),
+            priority: support_case.priority,
+            action,
+            labels,
+            transcript: transcript(support_case),
+        }
+    }
+}
+
+// Customer messages in order, followed by the latest reply
+pub fn transcript(support_case: &SupportCase) -> Vec<TranscriptEntry> {
+    let mut entries: Vec<TranscriptEntry> = Vec::from([TranscriptEntry {
+        role: TranscriptRole::Customer,
+        at: support_case.created_at,
+        text: support_case.customer_query.clone(),
+    }]);
+    entries.extend(
+        support_case
+            .customer_messages
+            .iter()
+            .map(|message| TranscriptEntry {
+                role: TranscriptRole::Customer,
+                at: message.at,
+                text: message.text.clone(),
+            }),
+    );
+    if let Some(response) = &support_case.support_response {
+        entries.push(TranscriptEntry {
+            role: TranscriptRole::Support,
+            at: support_case.updated_at,
+            text: response.clone(),
+        });
+    }
+    entries
+}
+
+// Where a ticket for the case was created
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TicketReference {
+    pub backend: String,
+    pub key: String,
+    // File path or URL of the ticket
+    pub location: String,
+    pub created_at: DateTime<Local>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TicketBackendConfig {
+    // One JSON file per ticket in 'directory'
+    File {
+        directory: String,
+    },
+    // Jira-style REST API: POST <base_url>/rest/api/2/issue
+    Http {
+        base_url: String,
+        project_key: String,
+        // Environment variable holding the API token, sent as a bearer token
+        #[serde(default)]
+        token_env: Option<String>,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TicketConfig {
+    pub backend: TicketBackendConfig,
+}
+
+impl Default for TicketConfig {
+    fn default() -> Self {
+        Self {
+            backend: TicketBackendConfig::File {
+                directory: "tickets".to_string(),
+            },
+        }
+    }
+}
+
+impl TicketConfig {
+    // Reads TICKET_CONFIG_PATH, falling back to built-in defaults
+    pub fn load() -> Self {
+        load_config("TICKET_CONFIG_PATH", DEFAULT_TICKET_CONFIG_PATH)
+    }
+}
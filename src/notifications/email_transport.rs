@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use std::error::Error;
+use std::time::Duration;
+
+use super::notifier::Transport;
+use crate::models::general::notification::Notification;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Sends notifications as plain-text email over SMTP
+#[derive(Debug)]
+pub struct EmailTransport {
+    host: String,
+    port: u16,
+    starttls: bool,
+    credentials: Option<(String, String)>,
+    from: String,
+    to: Vec<String>,
+}
+
+impl EmailTransport {
+    pub fn new(
+        host: &str,
+        port: u16,
+        starttls: bool,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &[String],
+    ) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            starttls,
+            credentials,
+            from: from.to_string(),
+            to: to.to_vec(),
+        }
+    }
+
+    fn mailer(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, Box<dyn Error + Send + Sync>> {
+        let mut builder = if self.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)
+        };
+        builder = builder.port(self.port).timeout(Some(SMTP_TIMEOUT));
+        if let Some((username, password)) = &self.credentials {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(builder.build())
+    }
+}
+
+#[async_trait]
+impl Transport for EmailTransport {
+    fn name(&self) -> String {
+        format!("email:{}", self.to.join(","))
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut message = Message::builder()
+            .from(self.from.parse()?)
+            .subject(notification.subject.clone())
+            .header(ContentType::TEXT_PLAIN);
+        for recipient in &self.to {
+            message = message.to(recipient.parse()?);
+        }
+        self.mailer()?
+            .send(message.body(notification.body.clone())?)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod email_transport;
+pub mod notifier;
+pub mod webhook_transport;
@@ -0,0 +1,326 @@
+use async_trait::async_trait;
+use chrono::Local;
+use std::error::Error;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use super::{email_transport::EmailTransport, webhook_transport::WebhookTransport};
+use crate::models::general::{
+    notification::{
+        ChannelConfig, DeliveryStatus, Notification, NotificationConfig, NotificationDelivery,
+    },
+    support_case::SupportCase,
+};
+
+// Channel that delivers notifications to upper management
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    // Identifies the channel in delivery records
+    fn name(&self) -> String;
+
+    async fn send(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+pub fn transport_from_config(config: &ChannelConfig) -> Box<dyn Transport> {
+    match config {
+        ChannelConfig::Email {
+            host,
+            port,
+            starttls,
+            username_env,
+            password_env,
+            from,
+            to,
+        } => {
+            let read =
+                |name: &Option<String>| name.as_ref().and_then(|name| dotenv::var(name).ok());
+            let credentials: Option<(String, String)> = read(username_env).zip(read(password_env));
+            Box::new(EmailTransport::new(
+                host,
+                *port,
+                *starttls,
+                credentials,
+                from,
+                to,
+            ))
+        }
+        ChannelConfig::Webhook { url, timeout_secs } => {
+            Box::new(WebhookTransport::new(url, *timeout_secs))
+        }
+    }
+}
+
+// Sends a case summary over every channel, retrying failed deliveries
+#[derive(Debug)]
+pub struct Notifier {
+    config: NotificationConfig,
+    transports: Vec<Box<dyn Transport>>,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig, transports: Vec<Box<dyn Transport>>) -> Self {
+        Self { config, transports }
+    }
+
+    pub fn from_config(config: NotificationConfig) -> Self {
+        let transports: Vec<Box<dyn Transport>> =
+            config.channels.iter().map(transport_from_config).collect();
+        Self::new(config, transports)
+    }
+
+    async fn deliver(
+        &self,
+        transport: &dyn Transport,
+        notification: &Notification,
+    ) -> NotificationDelivery {
+        let max_attempts: u32 = self.config.max_attempts.max(1);
+        let mut delay: Duration = Duration::from_millis(self.config.retry_delay_ms);
+        let mut error: Option<String> = None;
+        let mut attempts: u32 = 0;
+        while attempts < max_attempts {
+            if attempts > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            attempts += 1;
+            match transport.send(notification).await {
+                Ok(()) => {
+                    error = None;
+                    break;
+                }
+                Err(e) => error = Some(e.to_string()),
+            }
+        }
+        NotificationDelivery {
+            channel: transport.name(),
+            subject: notification.subject.clone(),
+            status: if error.is_none() {
+                DeliveryStatus::Delivered
+            } else {
+                DeliveryStatus::Failed
+            },
+            attempts,
+            error,
+            at: Local::now(),
+        }
+    }
+
+    // Channels that have not yet delivered a notification about the case
+    fn pending<'a>(&'a self, support_case: &SupportCase) -> Vec<&'a dyn Transport> {
+        self.transports
+            .iter()
+            .map(|transport| transport.as_ref())
+            .filter(|transport| {
+                !support_case.notifications.iter().any(|delivery| {
+                    delivery.channel == transport.name()
+                        && delivery.status == DeliveryStatus::Delivered
+                })
+            })
+            .collect()
+    }
+
+    pub fn has_pending(&self, support_case: &SupportCase) -> bool {
+        !self.pending(support_case).is_empty()
+    }
+
+    // Notifies upper management about the case over the channels that have not delivered yet
+    // and records every delivery on it
+    pub async fn notify(&self, support_case: &mut SupportCase) -> Vec<NotificationDelivery> {
+        let notification: Notification = self.config.notification_for(support_case);
+        let mut deliveries: Vec<NotificationDelivery> = Vec::from([]);
+        for transport in self.pending(support_case) {
+            deliveries.push(self.deliver(transport, &notification).await);
+        }
+        support_case
+            .notifications
+            .extend(deliveries.iter().cloned());
+        support_case.updated();
+        deliveries
+    }
+
+    pub fn has_channels(&self) -> bool {
+        !self.transports.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::escalation::{EscalationChoice, EscalationDecision};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    // SMTP sink that accepts one message and returns the received DATA
+    async fn smtp_sink() -> (u16, tokio::task::JoinHandle<String>) {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+            let mut data: String = String::new();
+            let mut in_data: bool = false;
+            while let Some(line) = lines.next_line().await.unwrap() {
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        writer.write_all(b"250 queued\r\n").await.unwrap();
+                    } else {
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    continue;
+                }
+                let command: String = line.to_uppercase();
+                if command.starts_with("EHLO") || command.starts_with("HELO") {
+                    writer.write_all(b"250 sink\r\n").await.unwrap();
+                } else if command.starts_with("DATA") {
+                    in_data = true;
+                    writer.write_all(b"354 go ahead\r\n").await.unwrap();
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 ok\r\n").await.unwrap();
+                }
+            }
+            data
+        });
+        (port, server)
+    }
+
+    // Webhook that answers each of 'statuses' in turn and counts the requests
+    async fn webhook(
+        statuses: &'static [&'static str],
+    ) -> (String, tokio::task::JoinHandle<usize>) {
+        webhook_at("127.0.0.1:0", statuses).await
+    }
+
+    async fn webhook_at(
+        address: &str,
+        statuses: &'static [&'static str],
+    ) -> (String, tokio::task::JoinHandle<usize>) {
+        let listener: TcpListener = TcpListener::bind(address).await.unwrap();
+        let url: String = format!("http://{}/hooks/management", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut received: Vec<u8> = Vec::from([]);
+                let mut buffer = [0u8; 4096];
+                while !String::from_utf8_lossy(&received).ends_with('}') {
+                    let read: usize = socket.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    received.extend_from_slice(&buffer[..read]);
+                }
+                let answer: String = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status
+                );
+                socket.write_all(answer.as_bytes()).await.unwrap();
+            }
+            statuses.len()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn tests_notifier_delivers_and_retries() {
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case.escalation_decision = Some(EscalationDecision::new(
+            EscalationChoice::UpperManagement,
+            "P1 case",
+            "",
+            1.0,
+        ));
+
+        let (port, sink) = smtp_sink().await;
+        let (url, hook) = webhook(&["500 Internal Server Error", "200 OK"]).await;
+        let config: NotificationConfig = NotificationConfig {
+            channels: Vec::from([
+                ChannelConfig::Email {
+                    host: "127.0.0.1".to_string(),
+                    port,
+                    starttls: false,
+                    username_env: None,
+                    password_env: None,
+                    from: "support@hotel.example".to_string(),
+                    to: Vec::from(["manager@hotel.example".to_string()]),
+                },
+                ChannelConfig::Webhook {
+                    url,
+                    timeout_secs: 5,
+                },
+            ]),
+            max_attempts: 2,
+            retry_delay_ms: 10,
+            ..Default::default()
+        };
+        let notifier: Notifier = Notifier::from_config(config);
+        let deliveries: Vec<NotificationDelivery> = notifier.notify(&mut support_case).await;
+
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[1].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[1].attempts, 2);
+        assert_eq!(support_case.notifications, deliveries);
+        assert_eq!(hook.await.unwrap(), 2);
+
+        // Long headers are folded onto continuation lines
+        let mail: String = sink.await.unwrap().replace("\n ", " ");
+        assert!(mail.contains(&format!(
+            "Upper management needed for case {}",
+            support_case.case_id
+        )));
+        assert!(mail.contains("The air conditioning in my room is broken."));
+
+        // Nobody listens anymore, so every attempt fails
+        let (url, hook) = webhook(&[]).await;
+        hook.await.unwrap();
+        let notifier: Notifier = Notifier::new(
+            NotificationConfig {
+                max_attempts: 2,
+                retry_delay_ms: 10,
+                ..Default::default()
+            },
+            Vec::from([Box::new(WebhookTransport::new(&url, 1)) as Box<dyn Transport>]),
+        );
+        let failed: Vec<NotificationDelivery> = notifier.notify(&mut support_case).await;
+        assert_eq!(failed[0].status, DeliveryStatus::Failed);
+        assert_eq!(failed[0].attempts, 2);
+        assert!(failed[0].error.is_some());
+
+        // Only the failed channel is tried again, once it listens; the delivered one is left alone
+        let address: &str = url.trim_start_matches("http://").split('/').next().unwrap();
+        let (url, hook) = webhook_at(address, &["200 OK"]).await;
+        let notifier: Notifier = Notifier::new(
+            NotificationConfig::default(),
+            Vec::from([
+                Box::new(WebhookTransport::new(&url, 5)) as Box<dyn Transport>,
+                transport_from_config(&ChannelConfig::Email {
+                    host: "127.0.0.1".to_string(),
+                    port,
+                    starttls: false,
+                    username_env: None,
+                    password_env: None,
+                    from: "support@hotel.example".to_string(),
+                    to: Vec::from(["manager@hotel.example".to_string()]),
+                }),
+            ]),
+        );
+        assert!(notifier.has_pending(&support_case));
+        let retried: Vec<NotificationDelivery> = notifier.notify(&mut support_case).await;
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].channel, failed[0].channel);
+        assert_eq!(retried[0].status, DeliveryStatus::Delivered);
+        assert_eq!(hook.await.unwrap(), 1);
+        assert!(!notifier.has_pending(&support_case));
+        assert!(notifier.notify(&mut support_case).await.is_empty());
+    }
+}
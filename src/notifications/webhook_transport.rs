@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use reqwest::{Client, Response};
+use std::error::Error;
+use std::time::Duration;
+
+use super::notifier::Transport;
+use crate::models::general::notification::Notification;
+
+// Posts notifications as JSON, f.e. to a chat or paging integration
+#[derive(Debug)]
+pub struct WebhookTransport {
+    url: String,
+    timeout: Duration,
+}
+
+impl WebhookTransport {
+    pub fn new(url: &str, timeout_secs: u64) -> Self {
+        Self {
+            url: url.to_string(),
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for WebhookTransport {
+    fn name(&self) -> String {
+        format!("webhook:{}", self.url)
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let client: Client = Client::builder().timeout(self.timeout).build()?;
+        let response: Response = client.post(&self.url).json(notification).send().await?;
+        if !response.status().is_success() {
+            return Err(format!("{} answered {}", self.url, response.status()).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::Local;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::models::general::{
+    attachment::{content_matches, extract_text, mime_type_for, Attachment, AttachmentConfig},
+    audit::sha256_hex,
+};
+
+// Keeps attachment files on local disk under '<storage_dir>/<case_id>/'
+pub struct AttachmentStore {
+    config: AttachmentConfig,
+}
+
+impl AttachmentStore {
+    pub fn new(config: AttachmentConfig) -> Self {
+        Self { config }
+    }
+
+    // Checks limits, copies the file into the store and extracts its text
+    pub fn save(&self, case_id: &Uuid, source: &Path) -> Result<Attachment, Box<dyn Error>> {
+        let file_name: String = source
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| format!("'{}' is not a file", source.display()))?
+            .to_string();
+        let mime_type: &str = mime_type_for(source);
+        let size_bytes: u64 = fs::metadata(source)?.len();
+        self.config.check(&file_name, mime_type, size_bytes)?;
+
+        let content: Vec<u8> = fs::read(source)?;
+        // A renamed file must not pass as the type of its new extension
+        if !content_matches(mime_type, &content) {
+            return Err(format!(
+                "Attachment '{}' does not contain what its extension says ({})",
+                file_name, mime_type
+            )
+            .into());
+        }
+        let attachment_id: Uuid = Uuid::new_v4();
+        let directory: PathBuf = Path::new(&self.config.storage_dir).join(case_id.to_string());
+        fs::create_dir_all(&directory)?;
+        let storage_path: PathBuf = directory.join(format!("{}-{}", attachment_id, file_name));
+        fs::write(&storage_path, &content)?;
+
+        Ok(Attachment {
+            attachment_id,
+            file_name,
+            mime_type: mime_type.to_string(),
+            size_bytes,
+            checksum: sha256_hex(&content),
+            storage_path: storage_path.to_string_lossy().to_string(),
+            extracted_text: extract_text(mime_type, &content, self.config.max_extracted_chars),
+            added_at: Local::now(),
+        })
+    }
+
+    // Content of a stored attachment, checked against its checksum
+    pub fn read(&self, attachment: &Attachment) -> Result<Vec<u8>, Box<dyn Error>> {
+        let content: Vec<u8> = fs::read(&attachment.storage_path)?;
+        if sha256_hex(&content) != attachment.checksum {
+            return Err(format!(
+                "Attachment '{}' does not match its checksum",
+                attachment.file_name
+            )
+            .into());
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_attachment_store() {
+        let root: PathBuf = std::env::temp_dir().join(format!("attachments-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let store: AttachmentStore = AttachmentStore::new(AttachmentConfig {
+            storage_dir: root.join("store").to_string_lossy().to_string(),
+            max_size_bytes: 64,
+            ..Default::default()
+        });
+        let case_id: Uuid = Uuid::new_v4();
+
+        let log: PathBuf = root.join("checkin.log");
+        fs::write(&log, "ERROR  key card\n  rejected at door 12").unwrap();
+        let attachment: Attachment = store.save(&case_id, &log).unwrap();
+        assert_eq!(attachment.mime_type, "text/plain");
+        assert_eq!(attachment.size_bytes, 37);
+        assert_eq!(
+            attachment.extracted_text.as_deref(),
+            Some("ERROR key card rejected at door 12")
+        );
+        assert_eq!(store.read(&attachment).unwrap().len(), 37);
+
+        fs::write(&attachment.storage_path, "changed").unwrap();
+        assert!(store.read(&attachment).is_err());
+
+        let binary: PathBuf = root.join("setup.exe");
+        fs::write(&binary, [0u8; 8]).unwrap();
+        assert!(store.save(&case_id, &binary).is_err());
+
+        let renamed: PathBuf = root.join("invoice.pdf");
+        fs::write(&renamed, b"MZ\x90\x00\x03\x00\x00\x00\x04\x00").unwrap();
+        assert!(store.save(&case_id, &renamed).is_err());
+        let renamed: PathBuf = root.join("notes.txt");
+        fs::write(&renamed, b"MZ\x90\x00\x03\x00\x00\x00\x04\x00").unwrap();
+        assert!(store.save(&case_id, &renamed).is_err());
+
+        let image: PathBuf = root.join("door.png");
+        fs::write(&image, b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR").unwrap();
+        assert_eq!(store.save(&case_id, &image).unwrap().mime_type, "image/png");
+
+        let large: PathBuf = root.join("large.txt");
+        fs::write(&large, "x".repeat(65)).unwrap();
+        assert!(store.save(&case_id, &large).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,32 @@
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::models::general::{
+    audit::{case_events, AuditEntry, AuditEvent},
+    support_case::SupportCase,
+};
+
+use super::case_store::CaseStore;
+
+// Entries can only be appended; stores never update or delete them
+pub trait AuditLog {
+    // Entries of one case or of the whole log, in sequence order
+    fn audit_entries(&self, case_id: Option<&Uuid>) -> Result<Vec<AuditEntry>, Box<dyn Error>>;
+
+    // Every stored case as (case_id, JSON as stored), to check against the log
+    fn case_snapshots(&self) -> Result<Vec<(Uuid, String)>, Box<dyn Error>>;
+}
+
+// Creates or updates the case and appends what changed to the audit log
+pub fn record_case(
+    store: &mut dyn CaseStore,
+    support_case: &SupportCase,
+    actor: &str,
+) -> Result<(), Box<dyn Error>> {
+    let previous: Option<SupportCase> = store.get(&support_case.case_id)?;
+    let events: Vec<AuditEvent> = case_events(previous.as_ref(), support_case, actor);
+    match previous {
+        Some(_) => store.update(support_case, events),
+        None => store.create(support_case, events),
+    }
+}
@@ -0,0 +1,267 @@
+use chrono::{DateTime, Local};
+use dotenv::dotenv;
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::models::general::{
+    audit::AuditEvent, case_status::CaseStatus, sentiment::Polarity, support_case::SupportCase,
+};
+
+use super::audit_log::AuditLog;
+use super::customer_store::CustomerStore;
+use super::{memory_store::MemoryCaseStore, sqlite_store::SqliteCaseStore};
+
+pub const DEFAULT_CASE_STORE_PATH: &str = "cases.db";
+
+#[derive(Debug, Clone, Default)]
+pub struct CaseFilter {
+    pub customer_id: Option<Uuid>,
+    pub status: Option<CaseStatus>,
+    pub sentiment: Option<Polarity>,
+    pub escalated: Option<bool>,
+    pub created_from: Option<DateTime<Local>>,
+    pub created_to: Option<DateTime<Local>>,
+}
+
+impl CaseFilter {
+    pub fn matches(&self, support_case: &SupportCase) -> bool {
+        if self.customer_id.is_some() && support_case.customer_id != self.customer_id {
+            return false;
+        }
+        if let Some(status) = self.status {
+            if support_case.status != status {
+                return false;
+            }
+        }
+        if let Some(polarity) = self.sentiment {
+            if support_case
+                .sentiment
+                .as_ref()
+                .map(|sentiment| sentiment.polarity)
+                != Some(polarity)
+            {
+                return false;
+            }
+        }
+        if let Some(escalated) = self.escalated {
+            if support_case.is_escalated() != escalated {
+                return false;
+            }
+        }
+        if let Some(from) = self.created_from {
+            if support_case.created_at < from {
+                return false;
+            }
+        }
+        if let Some(to) = self.created_to {
+            if support_case.created_at > to {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Customers and the audit log are stored alongside the cases, so every case store is
+// also a customer store and an audit log
+pub trait CaseStore: CustomerStore + AuditLog + Send {
+    // Persist a new case. Fails if the case already exists.
+    // Both writes append the audit entries for 'events' in the same transaction.
+    fn create(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    // Overwrite an existing case. Fails if the case is unknown.
+    fn update(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    fn get(&self, case_id: &Uuid) -> Result<Option<SupportCase>, Box<dyn Error>>;
+
+    // Cases matching the filter, oldest first
+    fn list(&self, filter: &CaseFilter) -> Result<Vec<SupportCase>, Box<dyn Error>>;
+
+    // Full-text search over customer queries and support responses
+    fn search(&self, text: &str) -> Result<Vec<SupportCase>, Box<dyn Error>>;
+}
+
+// Opens the store configured through CASE_STORE ("sqlite" or "memory") and CASE_STORE_PATH
+pub fn open_case_store() -> Result<Box<dyn CaseStore>, Box<dyn Error>> {
+    dotenv().ok();
+
+    let kind: String = dotenv::var("CASE_STORE").unwrap_or("sqlite".to_string());
+    match kind.as_str() {
+        "memory" => Ok(Box::new(MemoryCaseStore::new())),
+        "sqlite" => {
+            let path: String =
+                dotenv::var("CASE_STORE_PATH").unwrap_or(DEFAULT_CASE_STORE_PATH.to_string());
+            Ok(Box::new(SqliteCaseStore::open(&path)?))
+        }
+        _ => Err(format!("Unknown CASE_STORE '{}'", kind).into()),
+    }
+}
+
+// Checks every case store has to pass, run by the tests of each backend
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::models::general::audit::{verify_log, AuditAction, AuditEntry};
+    use crate::models::general::customer::{ContactChannel, ContactKind, Customer, CustomerTier};
+    use crate::models::general::sentiment::Sentiment;
+    use crate::storage::audit_log::record_case;
+    use chrono::Duration;
+
+    fn seed(store: &mut dyn CaseStore) -> Vec<SupportCase> {
+        let mut broken_ac: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        broken_ac.sentiment = Some(Sentiment::new(Polarity::Negative));
+        broken_ac
+            .transition(CaseStatus::Escalated, "Psychologist", "Negative sentiment")
+            .unwrap();
+        broken_ac.created_at -= Duration::days(2);
+
+        let mut late_checkout: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "Can I get a late checkout tomorrow?".to_string(),
+        );
+        late_checkout.sentiment = Some(Sentiment::new(Polarity::Positive));
+        late_checkout.support_response = Some("Late checkout until 2pm is confirmed.".to_string());
+        late_checkout
+            .transition(CaseStatus::InProgress, "Coordinator", "Handling request")
+            .unwrap();
+        late_checkout
+            .transition(CaseStatus::AwaitingCustomer, "Coordinator", "Response sent")
+            .unwrap();
+
+        store.create(&broken_ac, vec![]).unwrap();
+        store.create(&late_checkout, vec![]).unwrap();
+        vec![broken_ac, late_checkout]
+    }
+
+    pub fn check_store(store: &mut dyn CaseStore) {
+        let cases: Vec<SupportCase> = seed(store);
+
+        assert!(store.create(&cases[0], vec![]).is_err());
+        assert_eq!(
+            store.get(&cases[1].case_id).unwrap().unwrap().customer_query,
+            cases[1].customer_query
+        );
+        assert!(store.get(&Uuid::new_v4()).unwrap().is_none());
+
+        let all: Vec<SupportCase> = store.list(&CaseFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].case_id, cases[0].case_id);
+
+        let escalated: Vec<SupportCase> = store
+            .list(&CaseFilter {
+                escalated: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(escalated.len(), 1);
+        assert_eq!(escalated[0].case_id, cases[0].case_id);
+
+        let answered: Vec<SupportCase> = store
+            .list(&CaseFilter {
+                status: Some(CaseStatus::AwaitingCustomer),
+                sentiment: Some(Polarity::Positive),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(answered.len(), 1);
+
+        let recent: Vec<SupportCase> = store
+            .list(&CaseFilter {
+                created_from: Some(chrono::Local::now() - Duration::days(1)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].case_id, cases[1].case_id);
+
+        let found: Vec<SupportCase> = store.search("checkout confirmed").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].case_id, cases[1].case_id);
+        assert!(store.search("\"unbalanced").unwrap().is_empty());
+        // Both backends match whole words, case-insensitively, and hyphenated terms as phrases
+        assert_eq!(store.search("CHECKOUT").unwrap().len(), 1);
+        assert!(store.search("check").unwrap().is_empty());
+        assert_eq!(store.search("late-checkout").unwrap().len(), 1);
+        assert!(store.search("checkout-late").unwrap().is_empty());
+
+        let mut updated: SupportCase = cases[0].clone();
+        updated.support_response = Some("A technician is on the way.".to_string());
+        store.update(&updated, vec![]).unwrap();
+        assert_eq!(store.search("technician").unwrap().len(), 1);
+
+        let unknown: SupportCase = SupportCase::new("Hotel".to_string(), "Hi".to_string());
+        assert!(store.update(&unknown, vec![]).is_err());
+
+        let mut customer: Customer = Customer::new("Ada Lovelace".to_string());
+        customer.tier = CustomerTier::Vip;
+        customer.contact_channels.push(ContactChannel {
+            kind: ContactKind::Email,
+            value: "ada@example.com".to_string(),
+        });
+        store.create_customer(&customer).unwrap();
+        assert!(store.create_customer(&customer).is_err());
+        assert_eq!(
+            store
+                .find_customer_by_contact("ADA@example.com")
+                .unwrap()
+                .unwrap()
+                .customer_id,
+            customer.customer_id
+        );
+        assert!(store.find_customer_by_contact("bob@example.com").unwrap().is_none());
+
+        updated.attach_customer(&customer, &[]);
+        store.update(&updated, vec![]).unwrap();
+        let customer_cases: Vec<SupportCase> = store
+            .list(&CaseFilter {
+                customer_id: Some(customer.customer_id),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(customer_cases.len(), 1);
+        assert_eq!(customer_cases[0].customer_tier, CustomerTier::Vip);
+        assert_eq!(store.list_customers().unwrap().len(), 1);
+    }
+
+    pub fn check_audit_log(store: &mut dyn CaseStore) {
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        record_case(store, &support_case, "Coordinator").unwrap();
+        support_case
+            .transition(CaseStatus::Escalated, "Psychologist", "Negative sentiment")
+            .unwrap();
+        record_case(store, &support_case, "Coordinator").unwrap();
+
+        let entries: Vec<AuditEntry> = store.audit_entries(Some(&support_case.case_id)).unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.action).collect::<Vec<AuditAction>>(),
+            [
+                AuditAction::CaseCreated,
+                AuditAction::CaseUpdated,
+                AuditAction::StatusChanged
+            ]
+        );
+        let snapshots: Vec<(Uuid, String)> = store.case_snapshots().unwrap();
+        assert_eq!(entries[2].case_snapshot, snapshots[0].1);
+        assert!(verify_log(&entries, &snapshots).is_empty());
+
+        // Writes that bypass the audit log are reported
+        support_case.support_response = Some("Offer a full refund".to_string());
+        store.update(&support_case, vec![]).unwrap();
+        let snapshots: Vec<(Uuid, String)> = store.case_snapshots().unwrap();
+        assert_eq!(verify_log(&store.audit_entries(None).unwrap(), &snapshots).len(), 1);
+    }
+}
@@ -0,0 +1,20 @@
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::models::general::customer::Customer;
+
+pub trait CustomerStore {
+    // Persist a new customer. Fails if the customer already exists.
+    fn create_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>>;
+
+    // Overwrite an existing customer. Fails if the customer is unknown.
+    fn update_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>>;
+
+    fn get_customer(&self, customer_id: &Uuid) -> Result<Option<Customer>, Box<dyn Error>>;
+
+    // Look up a customer by any of their contact channel values (email, phone, ...)
+    fn find_customer_by_contact(&self, contact: &str) -> Result<Option<Customer>, Box<dyn Error>>;
+
+    // All customers, ordered by name
+    fn list_customers(&self) -> Result<Vec<Customer>, Box<dyn Error>>;
+}
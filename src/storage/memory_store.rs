@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::models::general::{
+    audit::{chain_entries, AuditEntry, AuditEvent},
+    customer::Customer,
+    support_case::SupportCase,
+};
+
+use super::audit_log::AuditLog;
+use super::case_store::{CaseFilter, CaseStore};
+use super::customer_store::CustomerStore;
+
+#[derive(Debug, Default)]
+pub struct MemoryCaseStore {
+    cases: HashMap<Uuid, SupportCase>,
+    customers: HashMap<Uuid, Customer>,
+    audit_log: Vec<AuditEntry>,
+}
+
+impl MemoryCaseStore {
+    pub fn new() -> Self {
+        Self {
+            cases: HashMap::new(),
+            customers: HashMap::new(),
+            audit_log: Vec::from([]),
+        }
+    }
+
+    // Lowercase words of a text, split like the SQLite FTS5 tokenizer does
+    fn words(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect()
+    }
+
+    // Everything that can fail happens before the case and its entries are stored
+    fn write_case(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        let snapshot: String = serde_json::to_string(support_case)?;
+        let entries: Vec<AuditEntry> = chain_entries(
+            self.audit_log.last().cloned(),
+            support_case.case_id,
+            &snapshot,
+            events,
+        );
+        self.cases.insert(support_case.case_id, support_case.clone());
+        self.audit_log.extend(entries);
+        Ok(())
+    }
+
+    fn sorted(mut cases: Vec<SupportCase>) -> Vec<SupportCase> {
+        cases.sort_by_key(|support_case| support_case.created_at);
+        cases
+    }
+}
+
+impl CaseStore for MemoryCaseStore {
+    fn create(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.cases.contains_key(&support_case.case_id) {
+            return Err(format!("Case {} already exists", support_case.case_id).into());
+        }
+        self.write_case(support_case, events)
+    }
+
+    fn update(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.cases.contains_key(&support_case.case_id) {
+            return Err(format!("Case {} not found", support_case.case_id).into());
+        }
+        self.write_case(support_case, events)
+    }
+
+    fn get(&self, case_id: &Uuid) -> Result<Option<SupportCase>, Box<dyn Error>> {
+        Ok(self.cases.get(case_id).cloned())
+    }
+
+    fn list(&self, filter: &CaseFilter) -> Result<Vec<SupportCase>, Box<dyn Error>> {
+        let cases: Vec<SupportCase> = self
+            .cases
+            .values()
+            .filter(|support_case| filter.matches(support_case))
+            .cloned()
+            .collect();
+        Ok(Self::sorted(cases))
+    }
+
+    fn search(&self, text: &str) -> Result<Vec<SupportCase>, Box<dyn Error>> {
+        // Every term is a phrase of whole words, as in the SQLite search
+        let terms: Vec<Vec<String>> = text
+            .split_whitespace()
+            .map(Self::words)
+            .filter(|words| !words.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let cases: Vec<SupportCase> = self
+            .cases
+            .values()
+            .filter(|support_case| {
+                let fields: [Vec<String>; 2] = [
+                    Self::words(&support_case.customer_query),
+                    Self::words(support_case.support_response.as_deref().unwrap_or("")),
+                ];
+                terms.iter().all(|term| {
+                    fields
+                        .iter()
+                        .any(|words| words.windows(term.len()).any(|window| window == term.as_slice()))
+                })
+            })
+            .cloned()
+            .collect();
+        Ok(Self::sorted(cases))
+    }
+}
+
+impl CustomerStore for MemoryCaseStore {
+    fn create_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>> {
+        if self.customers.contains_key(&customer.customer_id) {
+            return Err(format!("Customer {} already exists", customer.customer_id).into());
+        }
+        self.customers.insert(customer.customer_id, customer.clone());
+        Ok(())
+    }
+
+    fn update_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>> {
+        match self.customers.get_mut(&customer.customer_id) {
+            Some(stored) => {
+                *stored = customer.clone();
+                Ok(())
+            }
+            None => Err(format!("Customer {} not found", customer.customer_id).into()),
+        }
+    }
+
+    fn get_customer(&self, customer_id: &Uuid) -> Result<Option<Customer>, Box<dyn Error>> {
+        Ok(self.customers.get(customer_id).cloned())
+    }
+
+    fn find_customer_by_contact(&self, contact: &str) -> Result<Option<Customer>, Box<dyn Error>> {
+        Ok(self
+            .customers
+            .values()
+            .find(|customer| {
+                customer
+                    .contact_channels
+                    .iter()
+                    .any(|channel| channel.value.eq_ignore_ascii_case(contact))
+            })
+            .cloned())
+    }
+
+    fn list_customers(&self) -> Result<Vec<Customer>, Box<dyn Error>> {
+        let mut customers: Vec<Customer> = self.customers.values().cloned().collect();
+        customers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(customers)
+    }
+}
+
+impl AuditLog for MemoryCaseStore {
+    fn audit_entries(&self, case_id: Option<&Uuid>) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        Ok(self
+            .audit_log
+            .iter()
+            .filter(|entry| case_id.map(|case_id| entry.case_id == *case_id).unwrap_or(true))
+            .cloned()
+            .collect())
+    }
+
+    fn case_snapshots(&self) -> Result<Vec<(Uuid, String)>, Box<dyn Error>> {
+        let mut snapshots: Vec<(Uuid, String)> = vec![];
+        for support_case in self.cases.values() {
+            snapshots.push((support_case.case_id, serde_json::to_string(support_case)?));
+        }
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::case_store::tests::{check_audit_log, check_store};
+
+    #[test]
+    fn tests_memory_case_store() {
+        let mut store: MemoryCaseStore = MemoryCaseStore::new();
+        check_store(&mut store);
+
+        let mut store: MemoryCaseStore = MemoryCaseStore::new();
+        check_audit_log(&mut store);
+    }
+}
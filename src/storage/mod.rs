@@ -0,0 +1,6 @@
+pub mod attachment_store;
+pub mod audit_log;
+pub mod case_store;
+pub mod customer_store;
+pub mod memory_store;
+pub mod sqlite_store;
@@ -0,0 +1,559 @@
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
+use std::error::Error;
+use uuid::Uuid;
+
+use serde_json::json;
+
+use crate::models::general::{
+    audit::{chain_entries, AuditAction, AuditEntry, AuditEvent},
+    case_export::upgrade_v1_case,
+    customer::Customer,
+    support_case::SupportCase,
+};
+
+use super::audit_log::AuditLog;
+use super::case_store::{CaseFilter, CaseStore};
+use super::customer_store::CustomerStore;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS cases (
+    case_id TEXT PRIMARY KEY,
+    customer_id TEXT,
+    status TEXT NOT NULL,
+    sentiment TEXT,
+    escalated INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS cases_created_at ON cases (created_at);
+CREATE VIRTUAL TABLE IF NOT EXISTS cases_fts USING fts5 (
+    case_id UNINDEXED,
+    customer_query,
+    support_response
+);
+CREATE TABLE IF NOT EXISTS customers (
+    customer_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS customer_contacts (
+    customer_id TEXT NOT NULL,
+    value TEXT NOT NULL COLLATE NOCASE
+);
+CREATE INDEX IF NOT EXISTS customer_contacts_value ON customer_contacts (value);
+CREATE TABLE IF NOT EXISTS audit_log (
+    sequence INTEGER PRIMARY KEY,
+    case_id TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS audit_log_case_id ON audit_log (case_id);
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
+"#;
+
+// Columns added after the first release of the schema: (table, column, definition)
+const ADDED_COLUMNS: [(&str, &str, &str); 1] = [("cases", "customer_id", "TEXT")];
+
+enum Migration {
+    // Rewrites the stored JSON of every case and audits the cases it changed
+    Cases(fn(&mut serde_json::Value)),
+    // Updates columns derived from the case JSON; the JSON itself is left alone
+    Sql(&'static str),
+}
+
+// Data migrations, run once each in order; PRAGMA user_version counts the ones applied
+const MIGRATIONS: [Migration; 2] = [
+    // Cases stored before the lifecycle held escalation flags instead of a status
+    Migration::Cases(upgrade_v1_case),
+    // The sentiment column held the raw model answer, f.e. 'Negative', before it was typed
+    Migration::Sql(
+        "UPDATE cases SET sentiment = lower(sentiment) WHERE sentiment <> lower(sentiment);",
+    ),
+];
+
+// Writes the case row and its search entry, returning the JSON stored for the case
+fn write_row(conn: &Connection, support_case: &SupportCase) -> Result<String, Box<dyn Error>> {
+    let case_id: String = support_case.case_id.to_string();
+    let data: String = serde_json::to_string(support_case)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO cases (case_id, customer_id, status, sentiment, escalated, created_at, updated_at, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            case_id,
+            support_case.customer_id.map(|customer_id| customer_id.to_string()),
+            support_case.status.as_str(),
+            support_case.sentiment.as_ref().map(|sentiment| sentiment.polarity.as_str()),
+            support_case.is_escalated(),
+            support_case.created_at.timestamp_millis(),
+            support_case.updated_at.timestamp_millis(),
+            data,
+        ],
+    )?;
+    conn.execute("DELETE FROM cases_fts WHERE case_id = ?1", params![case_id])?;
+    conn.execute(
+        "INSERT INTO cases_fts (case_id, customer_query, support_response) VALUES (?1, ?2, ?3)",
+        params![
+            case_id,
+            support_case.customer_query,
+            support_case.support_response.as_deref().unwrap_or(""),
+        ],
+    )?;
+    Ok(data)
+}
+
+// Chains entries for 'events' after the last entry of the log and appends them
+fn append_entries(
+    conn: &Connection,
+    case_id: Uuid,
+    case_snapshot: &str,
+    events: Vec<AuditEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let last: Option<String> = conn
+        .query_row(
+            "SELECT data FROM audit_log ORDER BY sequence DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let last: Option<AuditEntry> = match last {
+        Some(data) => Some(serde_json::from_str(&data)?),
+        None => None,
+    };
+    for entry in chain_entries(last, case_id, case_snapshot, events) {
+        conn.execute(
+            "INSERT INTO audit_log (sequence, case_id, data) VALUES (?1, ?2, ?3)",
+            params![
+                entry.sequence as i64,
+                entry.case_id.to_string(),
+                serde_json::to_string(&entry)?,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// Rewrites every stored case with 'upgrade' and audits the cases that changed
+fn migrate_cases(
+    conn: &Connection,
+    migration: usize,
+    upgrade: fn(&mut serde_json::Value),
+) -> Result<(), Box<dyn Error>> {
+    let stored: Vec<String> = conn
+        .prepare("SELECT data FROM cases")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for data in stored {
+        let mut case: serde_json::Value = serde_json::from_str(&data)?;
+        upgrade(&mut case);
+        let support_case: SupportCase = serde_json::from_value(case)?;
+        let migrated: String = write_row(conn, &support_case)?;
+        if migrated != data {
+            let event: AuditEvent = AuditEvent {
+                action: AuditAction::CaseMigrated,
+                actor: "Migration".to_string(),
+                details: json!({ "migration": migration }),
+            };
+            append_entries(conn, support_case.case_id, &migrated, vec![event])?;
+        }
+    }
+    Ok(())
+}
+
+pub struct SqliteCaseStore {
+    conn: Connection,
+}
+
+impl SqliteCaseStore {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, Box<dyn Error>> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, Box<dyn Error>> {
+        conn.execute_batch(SCHEMA)?;
+        for (table, column, definition) in ADDED_COLUMNS {
+            let mut statement = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let columns: Vec<String> = statement
+                .query_map([], |row| row.get::<_, String>(1))?
+                .collect::<Result<_, _>>()?;
+            if !columns.iter().any(|existing| existing == column) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table, column, definition
+                ))?;
+            }
+        }
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS cases_customer_id ON cases (customer_id);")?;
+        let mut store: Self = Self { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    // Runs the migrations this database has not seen yet, each in its own transaction
+    fn migrate(&mut self) -> Result<(), Box<dyn Error>> {
+        let version: usize = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = self.conn.transaction()?;
+            match migration {
+                Migration::Cases(upgrade) => migrate_cases(&transaction, index + 1, *upgrade)?,
+                Migration::Sql(sql) => transaction.execute_batch(sql)?,
+            }
+            transaction.pragma_update(None, "user_version", (index + 1) as i64)?;
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+
+    // The case row, its search entry and its audit entries are written together or not at all
+    fn write_case(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        let transaction = self.conn.transaction()?;
+        let snapshot: String = write_row(&transaction, support_case)?;
+        append_entries(&transaction, support_case.case_id, &snapshot, events)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn exists(&self, case_id: &Uuid) -> Result<bool, Box<dyn Error>> {
+        let found: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM cases WHERE case_id = ?1",
+                params![case_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    fn write_customer(&self, customer: &Customer) -> Result<(), Box<dyn Error>> {
+        let customer_id: String = customer.customer_id.to_string();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO customers (customer_id, name, data) VALUES (?1, ?2, ?3)",
+            params![customer_id, customer.name, serde_json::to_string(customer)?],
+        )?;
+        self.conn.execute(
+            "DELETE FROM customer_contacts WHERE customer_id = ?1",
+            params![customer_id],
+        )?;
+        for channel in &customer.contact_channels {
+            self.conn.execute(
+                "INSERT INTO customer_contacts (customer_id, value) VALUES (?1, ?2)",
+                params![customer_id, channel.value],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn query_customers(&self, sql: &str, values: Vec<Value>) -> Result<Vec<Customer>, Box<dyn Error>> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+        let mut customers: Vec<Customer> = vec![];
+        for data in rows {
+            customers.push(serde_json::from_str(&data?)?);
+        }
+        Ok(customers)
+    }
+
+    fn query_audit_entries(&self, sql: &str, values: Vec<Value>) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+        let mut entries: Vec<AuditEntry> = vec![];
+        for data in rows {
+            entries.push(serde_json::from_str(&data?)?);
+        }
+        Ok(entries)
+    }
+
+    fn query_cases(&self, sql: &str, values: Vec<Value>) -> Result<Vec<SupportCase>, Box<dyn Error>> {
+        let mut statement = self.conn.prepare(sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+        let mut cases: Vec<SupportCase> = vec![];
+        for data in rows {
+            cases.push(serde_json::from_str(&data?)?);
+        }
+        Ok(cases)
+    }
+}
+
+impl CaseStore for SqliteCaseStore {
+    fn create(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.exists(&support_case.case_id)? {
+            return Err(format!("Case {} already exists", support_case.case_id).into());
+        }
+        self.write_case(support_case, events)
+    }
+
+    fn update(
+        &mut self,
+        support_case: &SupportCase,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.exists(&support_case.case_id)? {
+            return Err(format!("Case {} not found", support_case.case_id).into());
+        }
+        self.write_case(support_case, events)
+    }
+
+    fn get(&self, case_id: &Uuid) -> Result<Option<SupportCase>, Box<dyn Error>> {
+        let data: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT data FROM cases WHERE case_id = ?1",
+                params![case_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match data {
+            Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self, filter: &CaseFilter) -> Result<Vec<SupportCase>, Box<dyn Error>> {
+        let mut conditions: Vec<&str> = vec![];
+        let mut values: Vec<Value> = vec![];
+
+        if let Some(customer_id) = filter.customer_id {
+            conditions.push("customer_id = ?");
+            values.push(Value::Text(customer_id.to_string()));
+        }
+        if let Some(status) = filter.status {
+            conditions.push("status = ?");
+            values.push(Value::Text(status.as_str().to_string()));
+        }
+        if let Some(polarity) = filter.sentiment {
+            conditions.push("sentiment = ?");
+            values.push(Value::Text(polarity.as_str().to_string()));
+        }
+        if let Some(escalated) = filter.escalated {
+            conditions.push("escalated = ?");
+            values.push(Value::Integer(escalated as i64));
+        }
+        if let Some(from) = filter.created_from {
+            conditions.push("created_at >= ?");
+            values.push(Value::Integer(from.timestamp_millis()));
+        }
+        if let Some(to) = filter.created_to {
+            conditions.push("created_at <= ?");
+            values.push(Value::Integer(to.timestamp_millis()));
+        }
+
+        let mut sql: String = "SELECT data FROM cases".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY created_at");
+
+        self.query_cases(&sql, values)
+    }
+
+    fn search(&self, text: &str) -> Result<Vec<SupportCase>, Box<dyn Error>> {
+        // Quote every term so user input is never parsed as FTS5 query syntax
+        let terms: Vec<String> = text
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.query_cases(
+            "SELECT c.data FROM cases_fts f JOIN cases c ON c.case_id = f.case_id
+             WHERE cases_fts MATCH ?1 ORDER BY c.created_at",
+            vec![Value::Text(terms.join(" "))],
+        )
+    }
+}
+
+impl CustomerStore for SqliteCaseStore {
+    fn create_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>> {
+        if self.get_customer(&customer.customer_id)?.is_some() {
+            return Err(format!("Customer {} already exists", customer.customer_id).into());
+        }
+        self.write_customer(customer)
+    }
+
+    fn update_customer(&mut self, customer: &Customer) -> Result<(), Box<dyn Error>> {
+        if self.get_customer(&customer.customer_id)?.is_none() {
+            return Err(format!("Customer {} not found", customer.customer_id).into());
+        }
+        self.write_customer(customer)
+    }
+
+    fn get_customer(&self, customer_id: &Uuid) -> Result<Option<Customer>, Box<dyn Error>> {
+        Ok(self
+            .query_customers(
+                "SELECT data FROM customers WHERE customer_id = ?1",
+                vec![Value::Text(customer_id.to_string())],
+            )?
+            .pop())
+    }
+
+    fn find_customer_by_contact(&self, contact: &str) -> Result<Option<Customer>, Box<dyn Error>> {
+        Ok(self
+            .query_customers(
+                "SELECT c.data FROM customer_contacts cc JOIN customers c ON c.customer_id = cc.customer_id
+                 WHERE cc.value = ?1 LIMIT 1",
+                vec![Value::Text(contact.to_string())],
+            )?
+            .pop())
+    }
+
+    fn list_customers(&self) -> Result<Vec<Customer>, Box<dyn Error>> {
+        self.query_customers("SELECT data FROM customers ORDER BY name", vec![])
+    }
+}
+
+impl AuditLog for SqliteCaseStore {
+    fn audit_entries(&self, case_id: Option<&Uuid>) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        match case_id {
+            Some(case_id) => self.query_audit_entries(
+                "SELECT data FROM audit_log WHERE case_id = ?1 ORDER BY sequence",
+                vec![Value::Text(case_id.to_string())],
+            ),
+            None => self.query_audit_entries("SELECT data FROM audit_log ORDER BY sequence", vec![]),
+        }
+    }
+
+    fn case_snapshots(&self) -> Result<Vec<(Uuid, String)>, Box<dyn Error>> {
+        let mut statement = self.conn.prepare("SELECT case_id, data FROM cases")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut snapshots: Vec<(Uuid, String)> = vec![];
+        for row in rows {
+            let (case_id, data) = row?;
+            snapshots.push((Uuid::parse_str(&case_id)?, data));
+        }
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::audit::verify_log;
+    use crate::models::general::case_status::CaseStatus;
+    use crate::models::general::sentiment::Polarity;
+    use crate::storage::audit_log::record_case;
+    use crate::storage::case_store::tests::{check_audit_log, check_store};
+
+    #[test]
+    fn tests_sqlite_case_store() {
+        let mut store: SqliteCaseStore = SqliteCaseStore::open_in_memory().unwrap();
+        check_store(&mut store);
+
+        let mut store: SqliteCaseStore = SqliteCaseStore::open_in_memory().unwrap();
+        check_audit_log(&mut store);
+        assert!(store.conn.execute("DELETE FROM audit_log", []).is_err());
+
+        // A case is not stored when its audit entries cannot be written
+        store
+            .conn
+            .execute_batch(
+                "CREATE TEMP TRIGGER audit_log_unavailable BEFORE INSERT ON audit_log
+                 BEGIN SELECT RAISE(ABORT, 'audit log unavailable'); END;",
+            )
+            .unwrap();
+        let support_case: SupportCase = SupportCase::new("Hotel".to_string(), "Hi".to_string());
+        assert!(record_case(&mut store, &support_case, "Coordinator").is_err());
+        assert!(store.get(&support_case.case_id).unwrap().is_none());
+        assert!(store.search("Hi").unwrap().is_empty());
+    }
+
+    #[test]
+    fn tests_sqlite_migrations() {
+        let path: String = std::env::temp_dir()
+            .join(format!("cases-{}.db", Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let mut store: SqliteCaseStore = SqliteCaseStore::open(&path).unwrap();
+        let support_case: SupportCase = SupportCase::new("Hotel".to_string(), "Hi".to_string());
+        store.create(&support_case, vec![]).unwrap();
+
+        // A case as stored before the lifecycle, flagged for upper management and with the
+        // raw model answer as its sentiment
+        let mut v1: serde_json::Value = serde_json::to_value(&support_case).unwrap();
+        let fields = v1.as_object_mut().unwrap();
+        fields.remove("status");
+        fields.remove("status_history");
+        fields.remove("escalation_decision");
+        fields.insert("should_escalate".to_string(), serde_json::json!(true));
+        fields.insert("escalated".to_string(), serde_json::json!(true));
+        fields.insert("needs_upper_management_attention".to_string(), serde_json::json!(true));
+        fields.insert("sentiment".to_string(), serde_json::json!("Negative"));
+        let store_v1 = |store: &SqliteCaseStore| {
+            store
+                .conn
+                .execute(
+                    "UPDATE cases SET status = 'escalated', sentiment = 'Negative', data = ?1",
+                    params![v1.to_string()],
+                )
+                .unwrap();
+        };
+
+        // Applied migrations are not run again when the store is opened
+        store_v1(&store);
+        let store: SqliteCaseStore = SqliteCaseStore::open(&path).unwrap();
+        assert_eq!(store.get(&support_case.case_id).unwrap().unwrap().status, CaseStatus::New);
+
+        store_v1(&store);
+        store.conn.execute_batch("PRAGMA user_version = 0;").unwrap();
+        let store: SqliteCaseStore = SqliteCaseStore::open(&path).unwrap();
+        let migrated: SupportCase = store.get(&support_case.case_id).unwrap().unwrap();
+        assert_eq!(migrated.status, CaseStatus::Escalated);
+        assert!(migrated.needs_upper_management());
+        let data: String = store
+            .conn
+            .query_row("SELECT data FROM cases", [], |row| row.get(0))
+            .unwrap();
+        assert!(!data.contains("needs_upper_management_attention"));
+        // The rewrite is audited with the migrated case as stored
+        let entries: Vec<AuditEntry> = store.audit_entries(None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::CaseMigrated);
+        assert_eq!(entries[0].case_snapshot, data);
+        assert!(verify_log(&entries, &store.case_snapshots().unwrap()).is_empty());
+        let version: usize = store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        let negative: Vec<SupportCase> = store
+            .list(&CaseFilter {
+                sentiment: Some(Polarity::Negative),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(negative.len(), 1);
+
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
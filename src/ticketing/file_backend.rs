@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use chrono::Local;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::ticket_backend::TicketBackend;
+use crate::models::general::ticket::{Ticket, TicketReference};
+
+// Writes tickets as 'CASE-<n>.json' files numbered in creation order
+#[derive(Debug)]
+pub struct FileTicketBackend {
+    directory: String,
+}
+
+impl FileTicketBackend {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: directory.to_string(),
+        }
+    }
+
+    fn next_key(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let count: usize = fs::read_dir(&self.directory)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .count();
+        Ok(format!("CASE-{}", count + 1))
+    }
+}
+
+#[async_trait]
+impl TicketBackend for FileTicketBackend {
+    async fn create_ticket(
+        &self,
+        ticket: &Ticket,
+    ) -> Result<TicketReference, Box<dyn Error + Send + Sync>> {
+        fs::create_dir_all(&self.directory)?;
+        let key: String = self.next_key()?;
+        let path: PathBuf = Path::new(&self.directory).join(format!("{}.json", key));
+        fs::write(&path, serde_json::to_string_pretty(ticket)?)?;
+        Ok(TicketReference {
+            backend: "file".to_string(),
+            key,
+            location: path.to_string_lossy().to_string(),
+            created_at: Local::now(),
+        })
+    }
+}
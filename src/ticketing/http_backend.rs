@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use chrono::Local;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::Duration;
+
+use super::ticket_backend::TicketBackend;
+use crate::models::general::{
+    priority::Priority,
+    ticket::{Ticket, TicketReference, TranscriptRole},
+};
+
+// Answer of the issue endpoint, f.e. {"id": "10001", "key": "SUP-1", "self": "..."}
+#[derive(Deserialize)]
+struct CreatedIssue {
+    key: String,
+}
+
+// Creates issues through a Jira-style REST API
+#[derive(Debug)]
+pub struct HttpTicketBackend {
+    base_url: String,
+    project_key: String,
+    token: Option<String>,
+    timeout: Duration,
+}
+
+impl HttpTicketBackend {
+    pub fn new(
+        base_url: &str,
+        project_key: &str,
+        token: Option<String>,
+        timeout_secs: u64,
+    ) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            project_key: project_key.to_string(),
+            token,
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+
+    fn priority_name(priority: Priority) -> &'static str {
+        match priority {
+            Priority::P1 => "Highest",
+            Priority::P2 => "High",
+            Priority::P3 => "Medium",
+            Priority::P4 => "Low",
+        }
+    }
+
+    // Issue fields; the transcript is appended to the description
+    pub fn issue_body(&self, ticket: &Ticket) -> Value {
+        let transcript: Vec<String> = ticket
+            .transcript
+            .iter()
+            .map(|entry| {
+                let role: &str = match entry.role {
+                    TranscriptRole::Customer => "Customer",
+                    TranscriptRole::Support => "Support",
+                };
+                format!(
+                    "{} {}: {}",
+                    entry.at.format("%Y-%m-%d %H:%M"),
+                    role,
+                    entry.text
+                )
+            })
+            .collect();
+        json!({
+            "fields": {
+                "project": { "key": self.project_key },
+                "issuetype": { "name": "Task" },
+                "summary": ticket.title,
+                "description": format!("{}\n\nTranscript:\n{}", ticket.description, transcript.join("\n")),
+                "priority": { "name": Self::priority_name(ticket.priority) },
+                "labels": ticket.labels,
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl TicketBackend for HttpTicketBackend {
+    async fn create_ticket(
+        &self,
+        ticket: &Ticket,
+    ) -> Result<TicketReference, Box<dyn Error + Send + Sync>> {
+        let client: Client = Client::builder().timeout(self.timeout).build()?;
+        let mut request: RequestBuilder = client
+            .post(format!("{}/rest/api/2/issue", self.base_url))
+            .json(&self.issue_body(ticket));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response: Response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: String = response.text().await.unwrap_or_default();
+            return Err(format!("Ticket backend answered {}: {}", status, body).into());
+        }
+        let issue: CreatedIssue = response.json().await?;
+        Ok(TicketReference {
+            backend: "http".to_string(),
+            location: format!("{}/browse/{}", self.base_url, issue.key),
+            key: issue.key,
+            created_at: Local::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::support_case::SupportCase;
+    use crate::ticketing::file_backend::FileTicketBackend;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn tests_ticket_backends() {
+        let mut support_case: SupportCase = SupportCase::new(
+            "5 Star Hotel".to_string(),
+            "The air conditioning in my room is broken.".to_string(),
+        );
+        support_case.priority = Priority::P2;
+        support_case.add_customer_message("It is still not fixed!".to_string());
+        let ticket: Ticket = Ticket::from_case(&support_case);
+        assert_eq!(ticket.transcript.len(), 2);
+        assert!(ticket
+            .title
+            .starts_with("[P2] Escalation: The air conditioning"));
+
+        let directory = std::env::temp_dir().join(format!("tickets-{}", support_case.case_id));
+        let files: FileTicketBackend = FileTicketBackend::new(&directory.to_string_lossy());
+        let first: TicketReference = files.create_ticket(&ticket).await.unwrap();
+        let second: TicketReference = files.create_ticket(&ticket).await.unwrap();
+        assert_eq!(
+            (first.key.as_str(), second.key.as_str()),
+            ("CASE-1", "CASE-2")
+        );
+        let stored: Ticket =
+            serde_json::from_str(&std::fs::read_to_string(&first.location).unwrap()).unwrap();
+        assert_eq!(stored, ticket);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // Mock helpdesk that accepts one issue
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url: String = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received: Vec<u8> = Vec::from([]);
+            let mut buffer = [0u8; 4096];
+            while !String::from_utf8_lossy(&received).ends_with('}') {
+                let read: usize = socket.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buffer[..read]);
+            }
+            let body: &str =
+                r#"{"id":"10001","key":"SUP-7","self":"http://helpdesk/rest/api/2/issue/10001"}"#;
+            let answer: String = format!(
+                "HTTP/1.1 201 Created\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(answer.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&received).to_string()
+        });
+
+        let helpdesk: HttpTicketBackend =
+            HttpTicketBackend::new(&base_url, "SUP", Some("secret".to_string()), 5);
+        let reference: TicketReference = helpdesk.create_ticket(&ticket).await.unwrap();
+        assert_eq!(reference.key, "SUP-7");
+        assert_eq!(reference.location, format!("{}/browse/SUP-7", base_url));
+
+        let received: String = server.await.unwrap();
+        assert!(received.starts_with("POST /rest/api/2/issue"));
+        assert!(received
+            .to_lowercase()
+            .contains("authorization: bearer secret"));
+        let body: Value = serde_json::from_str(received.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["fields"]["project"]["key"], "SUP");
+        assert_eq!(body["fields"]["priority"]["name"], "High");
+        assert!(body["fields"]["description"]
+            .as_str()
+            .unwrap()
+            .contains("Customer: It is still not fixed!"));
+    }
+}
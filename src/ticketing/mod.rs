@@ -0,0 +1,3 @@
+pub mod file_backend;
+pub mod http_backend;
+pub mod ticket_backend;
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use std::error::Error;
+use std::fmt::Debug;
+
+use super::{file_backend::FileTicketBackend, http_backend::HttpTicketBackend};
+use crate::models::general::ticket::{Ticket, TicketBackendConfig, TicketReference};
+
+// Helpdesk that escalated cases are filed in
+#[async_trait]
+pub trait TicketBackend: Debug + Send + Sync {
+    async fn create_ticket(
+        &self,
+        ticket: &Ticket,
+    ) -> Result<TicketReference, Box<dyn Error + Send + Sync>>;
+}
+
+pub fn ticket_backend_from_config(config: &TicketBackendConfig) -> Box<dyn TicketBackend> {
+    match config {
+        TicketBackendConfig::File { directory } => Box::new(FileTicketBackend::new(directory)),
+        TicketBackendConfig::Http {
+            base_url,
+            project_key,
+            token_env,
+            timeout_secs,
+        } => Box::new(HttpTicketBackend::new(
+            base_url,
+            project_key,
+            token_env.as_ref().and_then(|name| dotenv::var(name).ok()),
+            *timeout_secs,
+        )),
+    }
+}